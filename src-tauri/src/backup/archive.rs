@@ -0,0 +1,241 @@
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_store::StoreExt;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use sha2::{Sha256, Digest};
+use anyhow::{Result, Context, anyhow};
+
+use crate::db::connection::{now_millis, Database};
+use crate::db::legacy_store::STORE_FILE;
+use crate::db::migrations;
+use crate::db::snapshot::{upgrade_database_file, RestoreCounts, RestoreMode};
+use crate::subscription::rules::{ChannelRule, DownloadRules};
+use crate::subscription::scheduler::{RefreshSettings, SubscriptionScheduler};
+
+// --- 定数定義 ---
+const BACKUP_FORMAT: &str = "yt-tur-backup";
+/// アーカイブの構成を変えたら上げる
+const BACKUP_FORMAT_VERSION: u32 = 1;
+
+const MANIFEST_NAME: &str = "manifest.json";
+const DATABASE_NAME: &str = "database.db";
+/// ストアの `settings` (フロントエンドの基本設定)
+const SETTINGS_NAME: &str = "settings.json";
+const DOWNLOAD_RULES_NAME: &str = "download-rules.json";
+const REFRESH_SETTINGS_NAME: &str = "subscription-refresh.json";
+
+
+// --- 型定義 ---
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupFile {
+    pub name: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+/// アーカイブの `manifest.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub format: String,
+    pub format_version: u32,
+    pub app_version: String,
+    /// データベースのスキーマのバージョン
+    pub schema_version: usize,
+    /// ミリ秒
+    pub created_at: i64,
+    pub files: Vec<BackupFile>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BackupImportReport {
+    pub mode: RestoreMode,
+    pub app_version: String,
+    pub created_at: i64,
+    /// バックアップ時のスキーマのバージョン (今より古ければ復元前に上げている)
+    pub schema_version: usize,
+    #[serde(flatten)]
+    pub counts: RestoreCounts,
+    pub download_rules: usize,
+    /// 基本設定と購読の更新設定を戻したか (置き換えのときだけ戻す)
+    pub settings_restored: bool,
+}
+
+
+// --- プライベートヘルパー関数 ---
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+fn temp_database_path() -> PathBuf {
+    std::env::temp_dir().join(format!("yt-tur-backup-{}.db", uuid::Uuid::new_v4()))
+}
+
+/// アーカイブに入れるファイルを集める
+fn collect_files(app_handle: &AppHandle) -> Result<Vec<(&'static str, Vec<u8>)>> {
+    let temp_path = temp_database_path();
+    app_handle.state::<Database>().snapshot_to(&temp_path)?;
+    let database = std::fs::read(&temp_path);
+    let _ = std::fs::remove_file(&temp_path);
+
+    let mut files = vec![(DATABASE_NAME, database?)];
+
+    if let Some(settings) = app_handle.store(STORE_FILE)?.get("settings") {
+        files.push((SETTINGS_NAME, serde_json::to_vec_pretty(&settings)?));
+    }
+    let rules = app_handle.state::<DownloadRules>().list();
+    files.push((DOWNLOAD_RULES_NAME, serde_json::to_vec_pretty(&rules)?));
+    let refresh_settings = app_handle.state::<SubscriptionScheduler>().settings();
+    files.push((REFRESH_SETTINGS_NAME, serde_json::to_vec_pretty(&refresh_settings)?));
+
+    Ok(files)
+}
+
+/// マニフェストを読み、記載された全ファイルの大きさとハッシュを確かめて取り出す
+fn read_archive(path: &Path) -> Result<(BackupManifest, HashMap<String, Vec<u8>>)> {
+    let file = std::fs::File::open(path)?;
+    let mut archive = zip::ZipArchive::new(file).context("zipファイルを開けません")?;
+
+    let manifest: BackupManifest = {
+        let entry = archive.by_name(MANIFEST_NAME)
+            .map_err(|_| anyhow!("バックアップのマニフェストがありません"))?;
+        serde_json::from_reader(entry).context("マニフェストの解析に失敗")?
+    };
+    if manifest.format != BACKUP_FORMAT {
+        return Err(anyhow!("このアプリのバックアップではありません"));
+    }
+    if manifest.format_version > BACKUP_FORMAT_VERSION {
+        return Err(anyhow!("新しいバージョンのアプリで作られたバックアップです (形式 {})", manifest.format_version));
+    }
+
+    let mut files = HashMap::new();
+    for file in &manifest.files {
+        let mut entry = archive.by_name(&file.name)
+            .map_err(|_| anyhow!("バックアップに {} が含まれていません", file.name))?;
+        let mut bytes = Vec::new();
+        entry.read_to_end(&mut bytes)?;
+        if bytes.len() as u64 != file.size || sha256_hex(&bytes) != file.sha256 {
+            return Err(anyhow!("チェックサムが一致しません: {}", file.name));
+        }
+        files.insert(file.name.clone(), bytes);
+    }
+    Ok((manifest, files))
+}
+
+
+// --- 公開関数 ---
+
+/// 設定・フォロー・プレイリスト・履歴・再生位置・自動ダウンロードのルールを1つのアーカイブに書き出す
+pub fn export_backup_to(app_handle: &AppHandle, output_path: &Path) -> Result<BackupManifest> {
+    let files = collect_files(app_handle)?;
+    let manifest = BackupManifest {
+        format: BACKUP_FORMAT.to_string(),
+        format_version: BACKUP_FORMAT_VERSION,
+        app_version: app_handle.package_info().version.to_string(),
+        schema_version: migrations::latest_version(),
+        created_at: now_millis(),
+        files: files.iter()
+            .map(|(name, bytes)| BackupFile { name: name.to_string(), size: bytes.len() as u64, sha256: sha256_hex(bytes) })
+            .collect(),
+    };
+
+    let temp_path = output_path.with_extension("zip.tmp");
+    {
+        let mut writer = zip::ZipWriter::new(std::fs::File::create(&temp_path)?);
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated);
+        writer.start_file(MANIFEST_NAME, options)?;
+        writer.write_all(&serde_json::to_vec_pretty(&manifest)?)?;
+        for (name, bytes) in &files {
+            writer.start_file(*name, options)?;
+            writer.write_all(bytes)?;
+        }
+        writer.finish()?;
+    }
+    std::fs::rename(&temp_path, output_path)?;
+    Ok(manifest)
+}
+
+/// バックアップを復元する
+///
+/// 古いスキーマのデータベースはマイグレーションで今のスキーマに上げてから取り込む。
+/// 基本設定と購読の更新設定は、置き換えのときだけ戻す。
+pub fn import_backup_from(app_handle: &AppHandle, source_path: &Path, mode: RestoreMode) -> Result<BackupImportReport> {
+    let (manifest, files) = read_archive(source_path)?;
+    let database_bytes = files.get(DATABASE_NAME)
+        .ok_or_else(|| anyhow!("バックアップに {} が含まれていません", DATABASE_NAME))?;
+
+    let temp_path = temp_database_path();
+    std::fs::write(&temp_path, database_bytes)?;
+    let restored = upgrade_database_file(&temp_path)
+        .and_then(|version| Ok((version, app_handle.state::<Database>().restore_from(&temp_path, mode)?)));
+    let _ = std::fs::remove_file(&temp_path);
+    let (schema_version, counts) = restored?;
+
+    let replace = mode == RestoreMode::Replace;
+    let download_rules = match files.get(DOWNLOAD_RULES_NAME) {
+        Some(bytes) => {
+            let rules: Vec<ChannelRule> = serde_json::from_slice(bytes)?;
+            app_handle.state::<DownloadRules>().restore(rules, replace)?
+        }
+        None => 0,
+    };
+
+    let mut settings_restored = false;
+    if replace {
+        if let Some(bytes) = files.get(SETTINGS_NAME) {
+            let store = app_handle.store(STORE_FILE)?;
+            store.set("settings", serde_json::from_slice::<serde_json::Value>(bytes)?);
+            store.save()?;
+            settings_restored = true;
+        }
+        if let Some(bytes) = files.get(REFRESH_SETTINGS_NAME) {
+            let settings: RefreshSettings = serde_json::from_slice(bytes)?;
+            app_handle.state::<SubscriptionScheduler>().set_settings(settings)?;
+            settings_restored = true;
+        }
+    }
+
+    let report = BackupImportReport {
+        mode,
+        app_version: manifest.app_version,
+        created_at: manifest.created_at,
+        schema_version,
+        counts,
+        download_rules,
+        settings_restored,
+    };
+    for event in ["follow_channels_changed", "playlists_changed", "history_changed"] {
+        app_handle.emit(event, ())?;
+    }
+    app_handle.emit("backup_restored", &report)?;
+    Ok(report)
+}
+
+
+// --- Tauri Commands ---
+
+#[tauri::command]
+pub async fn export_backup(app_handle: AppHandle, output_path: String) -> Result<BackupManifest, String> {
+    tauri::async_runtime::spawn_blocking(move || export_backup_to(&app_handle, Path::new(&output_path)))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+/// `mode` は "merge" (既定) か "replace"
+#[tauri::command]
+pub async fn import_backup(
+    app_handle: AppHandle,
+    source_path: String,
+    mode: Option<RestoreMode>,
+) -> Result<BackupImportReport, String> {
+    let mode = mode.unwrap_or(RestoreMode::Merge);
+    tauri::async_runtime::spawn_blocking(move || import_backup_from(&app_handle, Path::new(&source_path), mode))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,54 @@
+use tauri::{AppHandle, Manager};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+use rusqlite::{Connection, OptionalExtension};
+use anyhow::{Result, Context};
+
+use crate::db::{legacy_store, migrations};
+
+// --- 定数定義 ---
+const DATABASE_FILE_NAME: &str = "yt-tur.db";
+
+
+/// アプリのデータ (フォロー・プレイリスト・履歴) を保存するSQLiteデータベース
+pub struct Database {
+    conn: Mutex<Connection>,
+}
+
+/// 現在時刻 (ミリ秒)
+pub fn now_millis() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as i64).unwrap_or(0)
+}
+
+impl Database {
+    pub fn new(app_handle: &AppHandle) -> Result<Self> {
+        let app_data_dir = app_handle.path().app_data_dir().context("アプリデータディレクトリの取得に失敗")?;
+        std::fs::create_dir_all(&app_data_dir)?;
+
+        let mut conn = Connection::open(app_data_dir.join(DATABASE_FILE_NAME))?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.pragma_update(None, "foreign_keys", true)?;
+        migrations::migrate(&mut conn)?;
+
+        let database = Self { conn: Mutex::new(conn) };
+        legacy_store::import_once(app_handle, &database)?;
+        Ok(database)
+    }
+
+    pub fn connection(&self) -> MutexGuard<'_, Connection> {
+        self.conn.lock().unwrap()
+    }
+
+    pub fn get_meta(&self, key: &str) -> Result<Option<String>> {
+        let conn = self.connection();
+        Ok(conn.query_row("SELECT value FROM meta WHERE key = ?1", [key], |row| row.get(0)).optional()?)
+    }
+
+    pub fn set_meta(&self, key: &str, value: &str) -> Result<()> {
+        self.connection().execute(
+            "INSERT INTO meta (key, value) VALUES (?1, ?2) ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            [key, value],
+        )?;
+        Ok(())
+    }
+}
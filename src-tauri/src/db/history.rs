@@ -0,0 +1,179 @@
+use tauri::{AppHandle, Emitter, Manager, State};
+use std::collections::HashSet;
+use serde::{Deserialize, Serialize};
+use rusqlite::params;
+use anyhow::Result;
+
+use crate::db::connection::Database;
+use crate::library::index::extract_video_id;
+
+
+// --- 型定義 ---
+
+/// 視聴履歴の1件 (フロントエンドの `AppSettings.history` の1件)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    pub title: String,
+    pub url: String,
+    /// チャンネルID
+    pub id: String,
+    pub channel_name: String,
+    /// ミリ秒
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct HistoryImportReport {
+    pub added: usize,
+    /// 既に履歴にある、またはファイル内で重複
+    pub skipped: usize,
+}
+
+
+// --- プライベートヘルパー関数 ---
+
+/// 重複判定のキー (動画IDが取れなければタイトル)
+fn history_key(url: &str, title: &str) -> String {
+    extract_video_id(url).unwrap_or_else(|| title.to_string())
+}
+
+
+impl Database {
+    /// 新しい順に返す
+    pub fn list_history(&self, limit: Option<usize>, offset: usize) -> Result<Vec<HistoryEntry>> {
+        let conn = self.connection();
+        let mut statement = conn.prepare(
+            "SELECT title, url, channel_id, channel_name, watched_at FROM history
+             ORDER BY watched_at DESC LIMIT ?1 OFFSET ?2",
+        )?;
+        let limit = limit.map(|l| l as i64).unwrap_or(-1);
+        let entries = statement
+            .query_map(params![limit, offset as i64], |row| {
+                Ok(HistoryEntry {
+                    title: row.get(0)?,
+                    url: row.get(1)?,
+                    id: row.get(2)?,
+                    channel_name: row.get(3)?,
+                    timestamp: row.get::<_, i64>(4)? as u64,
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(entries)
+    }
+
+    /// 履歴に追加する。同じ動画が既にあれば視聴日時を更新して先頭に移す
+    pub fn add_history(&self, entry: &HistoryEntry) -> Result<()> {
+        self.connection().execute(
+            "INSERT INTO history (key, video_id, title, url, channel_id, channel_name, watched_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT(key) DO UPDATE SET
+                title = excluded.title, url = excluded.url, channel_id = excluded.channel_id,
+                channel_name = excluded.channel_name, watched_at = excluded.watched_at",
+            params![
+                history_key(&entry.url, &entry.title),
+                extract_video_id(&entry.url),
+                entry.title,
+                entry.url,
+                entry.id,
+                entry.channel_name,
+                entry.timestamp as i64,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// 取り込んだ履歴を加える。同じ動画は既存のものを残し、取り込む側では最も新しい視聴だけを使う
+    pub fn import_history(&self, mut entries: Vec<HistoryEntry>) -> Result<HistoryImportReport> {
+        entries.sort_by_key(|e| std::cmp::Reverse(e.timestamp));
+
+        let mut conn = self.connection();
+        let tx = conn.transaction()?;
+        let mut report = HistoryImportReport::default();
+        {
+            let mut statement = tx.prepare(
+                "INSERT OR IGNORE INTO history (key, video_id, title, url, channel_id, channel_name, watched_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            )?;
+            for entry in &entries {
+                let inserted = statement.execute(params![
+                    history_key(&entry.url, &entry.title),
+                    extract_video_id(&entry.url),
+                    entry.title,
+                    entry.url,
+                    entry.id,
+                    entry.channel_name,
+                    entry.timestamp as i64,
+                ])?;
+                if inserted > 0 {
+                    report.added += 1;
+                } else {
+                    report.skipped += 1;
+                }
+            }
+        }
+        tx.commit()?;
+        Ok(report)
+    }
+
+    pub fn remove_history(&self, url: &str, title: &str) -> Result<()> {
+        self.connection().execute("DELETE FROM history WHERE key = ?1", [history_key(url, title)])?;
+        Ok(())
+    }
+
+    pub fn clear_history(&self) -> Result<()> {
+        self.connection().execute("DELETE FROM history", [])?;
+        Ok(())
+    }
+
+    /// 履歴にある動画と、視聴済みにした動画
+    pub fn watched_video_ids(&self) -> Result<HashSet<String>> {
+        let conn = self.connection();
+        let mut statement = conn.prepare(
+            "SELECT video_id FROM history WHERE video_id IS NOT NULL
+             UNION SELECT video_id FROM playback_positions WHERE watched = 1",
+        )?;
+        let ids = statement.query_map([], |row| row.get(0))?.collect::<rusqlite::Result<_>>()?;
+        Ok(ids)
+    }
+}
+
+
+// --- 公開関数 ---
+
+/// 取り込んだ履歴を保存し、フロントエンドに `history_changed` で知らせる
+pub fn merge_history(app_handle: &AppHandle, entries: Vec<HistoryEntry>) -> Result<HistoryImportReport> {
+    let report = app_handle.state::<Database>().import_history(entries)?;
+    if report.added > 0 {
+        app_handle.emit("history_changed", ())?;
+    }
+    Ok(report)
+}
+
+/// 視聴履歴にある動画IDを取得する
+pub fn get_watched_video_ids(app_handle: &AppHandle) -> Result<HashSet<String>> {
+    app_handle.state::<Database>().watched_video_ids()
+}
+
+
+// --- Tauri Commands ---
+
+#[tauri::command]
+pub fn list_history(database: State<'_, Database>, limit: Option<usize>, offset: Option<usize>) -> Result<Vec<HistoryEntry>, String> {
+    database.list_history(limit, offset.unwrap_or(0)).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn add_history(database: State<'_, Database>, entry: HistoryEntry) -> Result<(), String> {
+    database.add_history(&entry).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn remove_history(database: State<'_, Database>, url: String, title: String) -> Result<(), String> {
+    database.remove_history(&url, &title).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn clear_history(database: State<'_, Database>) -> Result<(), String> {
+    database.clear_history().map_err(|e| e.to_string())
+}
@@ -0,0 +1,51 @@
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+use serde_json::Value;
+use anyhow::Result;
+
+use crate::db::connection::{now_millis, Database};
+use crate::db::history::HistoryEntry;
+use crate::db::playlists::Playlist;
+use crate::db::subscriptions::FollowedChannel;
+
+// --- 定数定義 ---
+// 以前 useSettings.ts が全データを書き込んでいたストア (設定は今もここに保存する)
+pub const STORE_FILE: &str = "app-settings.json";
+const IMPORTED_META_KEY: &str = "legacy_store_imported";
+
+
+// --- 公開関数 ---
+
+/// ストアの `followChannel`・`playlist`・`history` を一度だけデータベースに取り込む
+///
+/// ストア側のデータは消さずに残す (古いバージョンに戻したときのため)。
+pub fn import_once(app_handle: &AppHandle, database: &Database) -> Result<()> {
+    if database.get_meta(IMPORTED_META_KEY)?.is_some() {
+        return Ok(());
+    }
+    let store = app_handle.store(STORE_FILE)?;
+
+    if let Some(Value::Array(items)) = store.get("followChannel") {
+        let channels: Vec<FollowedChannel> = items.into_iter().filter_map(|i| serde_json::from_value(i).ok()).collect();
+        let added = database.add_subscriptions(&channels)?;
+        println!("Imported {} followed channels from the settings store", added);
+    }
+
+    if let Some(Value::Object(playlists)) = store.get("playlist") {
+        for (id, value) in playlists {
+            match serde_json::from_value::<Playlist>(value) {
+                Ok(playlist) => database.save_playlist(&id, &playlist)?,
+                Err(e) => println!("Skipping playlist {} from the settings store: {}", id, e),
+            }
+        }
+    }
+
+    if let Some(Value::Array(items)) = store.get("history") {
+        let entries: Vec<HistoryEntry> = items.into_iter().filter_map(|i| serde_json::from_value(i).ok()).collect();
+        let report = database.import_history(entries)?;
+        println!("Imported {} history entries from the settings store", report.added);
+    }
+
+    database.set_meta(IMPORTED_META_KEY, &now_millis().to_string())?;
+    Ok(())
+}
@@ -0,0 +1,185 @@
+use rusqlite::Connection;
+use anyhow::{Result, anyhow};
+
+// --- 定数定義 ---
+
+/// 順番に適用するマイグレーション。適用済みの数は `PRAGMA user_version` に記録する
+///
+/// 既存の要素は書き換えず、変更は末尾に追加する。
+const MIGRATIONS: &[&str] = &[
+    // 1: フォロー・プレイリスト・履歴
+    r#"
+    CREATE TABLE meta (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+
+    CREATE TABLE channels (
+        id TEXT PRIMARY KEY,
+        channel_name TEXT NOT NULL,
+        cache TEXT,
+        position INTEGER NOT NULL,
+        followed_at INTEGER NOT NULL
+    );
+
+    CREATE TABLE playlists (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        thumbnail TEXT NOT NULL DEFAULT '',
+        created_at INTEGER NOT NULL
+    );
+
+    CREATE TABLE playlist_items (
+        playlist_id TEXT NOT NULL REFERENCES playlists(id) ON DELETE CASCADE,
+        video_id TEXT NOT NULL,
+        title TEXT NOT NULL,
+        url TEXT NOT NULL,
+        channel_id TEXT NOT NULL,
+        channel_name TEXT NOT NULL,
+        position INTEGER NOT NULL,
+        PRIMARY KEY (playlist_id, video_id)
+    );
+
+    CREATE TABLE history (
+        key TEXT PRIMARY KEY,
+        video_id TEXT,
+        title TEXT NOT NULL,
+        url TEXT NOT NULL,
+        channel_id TEXT NOT NULL,
+        channel_name TEXT NOT NULL,
+        watched_at INTEGER NOT NULL
+    );
+    CREATE INDEX history_watched_at ON history (watched_at DESC);
+    CREATE INDEX history_video_id ON history (video_id);
+    "#,
+    // 2: 再生位置
+    r#"
+    CREATE TABLE playback_positions (
+        video_id TEXT PRIMARY KEY,
+        position REAL NOT NULL,
+        duration REAL,
+        watched INTEGER NOT NULL DEFAULT 0,
+        updated_at INTEGER NOT NULL
+    );
+    CREATE INDEX playback_positions_updated_at ON playback_positions (updated_at DESC);
+    "#,
+    // 3: 全文検索 (trigramなので日本語も部分一致で引ける)
+    //
+    // search_index の rowid は「元の表の rowid * 4 + 種類」にして、トリガーから直接消せるようにする。
+    r#"
+    CREATE TABLE video_metadata (
+        video_id TEXT PRIMARY KEY,
+        title TEXT NOT NULL,
+        description TEXT NOT NULL DEFAULT '',
+        channel_id TEXT NOT NULL DEFAULT '',
+        channel_name TEXT NOT NULL DEFAULT '',
+        url TEXT NOT NULL,
+        thumbnail TEXT,
+        updated_at INTEGER NOT NULL
+    );
+
+    CREATE VIRTUAL TABLE search_index USING fts5(
+        kind UNINDEXED,
+        video_id UNINDEXED,
+        channel_id UNINDEXED,
+        playlist_id UNINDEXED,
+        url UNINDEXED,
+        title,
+        channel_name,
+        description,
+        tokenize = 'trigram'
+    );
+
+    CREATE TRIGGER history_search_insert AFTER INSERT ON history BEGIN
+        INSERT INTO search_index (rowid, kind, video_id, channel_id, playlist_id, url, title, channel_name, description)
+            VALUES (new.rowid * 4 + 0, 'history', new.video_id, new.channel_id, NULL, new.url, new.title, new.channel_name, '');
+    END;
+    CREATE TRIGGER history_search_delete AFTER DELETE ON history BEGIN
+        DELETE FROM search_index WHERE rowid = old.rowid * 4 + 0;
+    END;
+    CREATE TRIGGER history_search_update AFTER UPDATE ON history BEGIN
+        DELETE FROM search_index WHERE rowid = old.rowid * 4 + 0;
+        INSERT INTO search_index (rowid, kind, video_id, channel_id, playlist_id, url, title, channel_name, description)
+            VALUES (new.rowid * 4 + 0, 'history', new.video_id, new.channel_id, NULL, new.url, new.title, new.channel_name, '');
+    END;
+
+    CREATE TRIGGER playlist_items_search_insert AFTER INSERT ON playlist_items BEGIN
+        INSERT INTO search_index (rowid, kind, video_id, channel_id, playlist_id, url, title, channel_name, description)
+            VALUES (new.rowid * 4 + 1, 'playlist_item', new.video_id, new.channel_id, new.playlist_id, new.url, new.title, new.channel_name, '');
+    END;
+    CREATE TRIGGER playlist_items_search_delete AFTER DELETE ON playlist_items BEGIN
+        DELETE FROM search_index WHERE rowid = old.rowid * 4 + 1;
+    END;
+    CREATE TRIGGER playlist_items_search_update AFTER UPDATE ON playlist_items BEGIN
+        DELETE FROM search_index WHERE rowid = old.rowid * 4 + 1;
+        INSERT INTO search_index (rowid, kind, video_id, channel_id, playlist_id, url, title, channel_name, description)
+            VALUES (new.rowid * 4 + 1, 'playlist_item', new.video_id, new.channel_id, new.playlist_id, new.url, new.title, new.channel_name, '');
+    END;
+
+    CREATE TRIGGER channels_search_insert AFTER INSERT ON channels BEGIN
+        INSERT INTO search_index (rowid, kind, video_id, channel_id, playlist_id, url, title, channel_name, description)
+            VALUES (new.rowid * 4 + 2, 'channel', NULL, new.id, NULL, 'https://www.youtube.com/channel/' || new.id,
+                new.channel_name, COALESCE(json_extract(new.cache, '$.atId'), ''), COALESCE(json_extract(new.cache, '$.description'), ''));
+    END;
+    CREATE TRIGGER channels_search_delete AFTER DELETE ON channels BEGIN
+        DELETE FROM search_index WHERE rowid = old.rowid * 4 + 2;
+    END;
+    CREATE TRIGGER channels_search_update AFTER UPDATE ON channels BEGIN
+        DELETE FROM search_index WHERE rowid = old.rowid * 4 + 2;
+        INSERT INTO search_index (rowid, kind, video_id, channel_id, playlist_id, url, title, channel_name, description)
+            VALUES (new.rowid * 4 + 2, 'channel', NULL, new.id, NULL, 'https://www.youtube.com/channel/' || new.id,
+                new.channel_name, COALESCE(json_extract(new.cache, '$.atId'), ''), COALESCE(json_extract(new.cache, '$.description'), ''));
+    END;
+
+    CREATE TRIGGER video_metadata_search_insert AFTER INSERT ON video_metadata BEGIN
+        INSERT INTO search_index (rowid, kind, video_id, channel_id, playlist_id, url, title, channel_name, description)
+            VALUES (new.rowid * 4 + 3, 'video', new.video_id, new.channel_id, NULL, new.url, new.title, new.channel_name, new.description);
+    END;
+    CREATE TRIGGER video_metadata_search_delete AFTER DELETE ON video_metadata BEGIN
+        DELETE FROM search_index WHERE rowid = old.rowid * 4 + 3;
+    END;
+    CREATE TRIGGER video_metadata_search_update AFTER UPDATE ON video_metadata BEGIN
+        DELETE FROM search_index WHERE rowid = old.rowid * 4 + 3;
+        INSERT INTO search_index (rowid, kind, video_id, channel_id, playlist_id, url, title, channel_name, description)
+            VALUES (new.rowid * 4 + 3, 'video', new.video_id, new.channel_id, NULL, new.url, new.title, new.channel_name, new.description);
+    END;
+
+    INSERT INTO search_index (rowid, kind, video_id, channel_id, playlist_id, url, title, channel_name, description)
+    SELECT rowid * 4 + 0, 'history', video_id, channel_id, NULL, url, title, channel_name, '' FROM history;
+    INSERT INTO search_index (rowid, kind, video_id, channel_id, playlist_id, url, title, channel_name, description)
+    SELECT rowid * 4 + 1, 'playlist_item', video_id, channel_id, playlist_id, url, title, channel_name, '' FROM playlist_items;
+    INSERT INTO search_index (rowid, kind, video_id, channel_id, playlist_id, url, title, channel_name, description)
+    SELECT rowid * 4 + 2, 'channel', NULL, id, NULL, 'https://www.youtube.com/channel/' || id,
+        channel_name, COALESCE(json_extract(cache, '$.atId'), ''), COALESCE(json_extract(cache, '$.description'), '')
+    FROM channels;
+    "#,
+];
+
+
+// --- 公開関数 ---
+
+pub fn schema_version(conn: &Connection) -> Result<usize> {
+    let version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    Ok(version as usize)
+}
+
+pub fn latest_version() -> usize {
+    MIGRATIONS.len()
+}
+
+/// 未適用のマイグレーションを1つずつトランザクションで適用する
+pub fn migrate(conn: &mut Connection) -> Result<()> {
+    let current = schema_version(conn)?;
+    if current > latest_version() {
+        return Err(anyhow!("データベースのバージョン ({}) がこのアプリより新しいです", current));
+    }
+
+    for (index, sql) in MIGRATIONS.iter().enumerate().skip(current) {
+        println!("Applying database migration {}", index + 1);
+        let tx = conn.transaction()?;
+        tx.execute_batch(sql)?;
+        tx.pragma_update(None, "user_version", (index + 1) as i64)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
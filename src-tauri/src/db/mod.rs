@@ -0,0 +1,9 @@
+pub mod connection;
+pub mod history;
+pub mod legacy_store;
+pub mod migrations;
+pub mod playback;
+pub mod playlists;
+pub mod search;
+pub mod snapshot;
+pub mod subscriptions;
@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::State;
+use serde::Serialize;
+use rusqlite::{params, OptionalExtension, Row};
+use once_cell::sync::Lazy;
+use anyhow::Result;
+
+use crate::db::connection::{now_millis, Database};
+
+// --- 定数定義 ---
+/// プレイヤーからの位置の更新を書き込む間隔 (動画ごと)
+const WRITE_INTERVAL: Duration = Duration::from_secs(10);
+/// この割合まで再生したら視聴済みにする
+const WATCHED_RATIO: f64 = 0.9;
+/// これより手前なら続きから再生しない (秒)
+const MIN_RESUME_POSITION: f64 = 10.0;
+const DEFAULT_CONTINUE_WATCHING_LIMIT: usize = 20;
+
+// 動画IDごとの最後に書き込んだ時刻
+static LAST_WRITES: Lazy<Mutex<HashMap<String, Instant>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+
+// --- 型定義 ---
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WatchProgress {
+    pub video_id: String,
+    /// 秒
+    pub position: f64,
+    /// 秒
+    pub duration: Option<f64>,
+    pub watched: bool,
+    /// 0〜100 (視聴済みなら100)
+    pub percent: f64,
+    /// ミリ秒
+    pub updated_at: i64,
+}
+
+/// 「続きから見る」の1件 (タイトルなどは履歴から取る)
+#[derive(Debug, Clone, Serialize)]
+pub struct ContinueWatchingEntry {
+    #[serde(flatten)]
+    pub progress: WatchProgress,
+    pub title: String,
+    pub url: String,
+    pub channel_id: String,
+    pub channel_name: String,
+}
+
+
+// --- プライベートヘルパー関数 ---
+
+fn watched_percent(position: f64, duration: Option<f64>, watched: bool) -> f64 {
+    if watched {
+        return 100.0;
+    }
+    match duration {
+        Some(d) if d > 0.0 => (position / d * 100.0).clamp(0.0, 100.0),
+        _ => 0.0,
+    }
+}
+
+/// `video_id, position, duration, watched, updated_at` の順の行を読む
+fn progress_from_row(row: &Row) -> rusqlite::Result<WatchProgress> {
+    let position: f64 = row.get(1)?;
+    let duration: Option<f64> = row.get(2)?;
+    let watched: bool = row.get(3)?;
+    Ok(WatchProgress {
+        video_id: row.get(0)?,
+        position,
+        duration,
+        watched,
+        percent: watched_percent(position, duration, watched),
+        updated_at: row.get(4)?,
+    })
+}
+
+/// 前回の書き込みから `WRITE_INTERVAL` 経っていれば書き込み時刻を記録して true を返す
+fn should_write(video_id: &str, force: bool) -> bool {
+    let mut last_writes = LAST_WRITES.lock().unwrap();
+    let now = Instant::now();
+    if !force && last_writes.get(video_id).is_some_and(|t| now.duration_since(*t) < WRITE_INTERVAL) {
+        return false;
+    }
+    // 間隔を過ぎた記録は無いのと同じなので、ここで捨てて増え続けないようにする
+    last_writes.retain(|_, t| now.duration_since(*t) < WRITE_INTERVAL);
+    last_writes.insert(video_id.to_string(), now);
+    true
+}
+
+/// 視聴済みにした・位置を戻した動画は、次の位置の更新をすぐに書き込めるようにする
+fn forget_write(video_id: &str) {
+    LAST_WRITES.lock().unwrap().remove(video_id);
+}
+
+
+impl Database {
+    /// 再生位置を保存する。`WATCHED_RATIO` を超えたら視聴済みにする (一度視聴済みになったら戻さない)
+    pub fn save_playback_position(&self, video_id: &str, position: f64, duration: Option<f64>) -> Result<()> {
+        let duration = duration.filter(|d| d.is_finite() && *d > 0.0);
+        let reached_end = duration.is_some_and(|d| position >= d * WATCHED_RATIO);
+        self.connection().execute(
+            "INSERT INTO playback_positions (video_id, position, duration, watched, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(video_id) DO UPDATE SET
+                position = excluded.position,
+                duration = COALESCE(excluded.duration, playback_positions.duration),
+                watched = playback_positions.watched OR excluded.watched,
+                updated_at = excluded.updated_at",
+            params![video_id, position.max(0.0), duration, reached_end, now_millis()],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_playback(&self, video_id: &str) -> Result<Option<WatchProgress>> {
+        let conn = self.connection();
+        Ok(conn.query_row(
+            "SELECT video_id, position, duration, watched, updated_at FROM playback_positions WHERE video_id = ?1",
+            [video_id],
+            progress_from_row,
+        ).optional()?)
+    }
+
+    /// 視聴済みにする、または視聴済みを外す。外したときは最初から再生するように位置も戻す
+    pub fn set_watched(&self, video_id: &str, watched: bool) -> Result<()> {
+        self.connection().execute(
+            "INSERT INTO playback_positions (video_id, position, duration, watched, updated_at)
+             VALUES (?1, 0, NULL, ?2, ?3)
+             ON CONFLICT(video_id) DO UPDATE SET
+                watched = excluded.watched,
+                position = CASE WHEN excluded.watched THEN playback_positions.position ELSE 0 END,
+                updated_at = excluded.updated_at",
+            params![video_id, watched, now_millis()],
+        )?;
+        Ok(())
+    }
+
+    pub fn watch_progress(&self, video_ids: &[String]) -> Result<HashMap<String, WatchProgress>> {
+        let conn = self.connection();
+        let mut statement = conn.prepare(
+            "SELECT video_id, position, duration, watched, updated_at FROM playback_positions WHERE video_id = ?1",
+        )?;
+        let mut result = HashMap::new();
+        for video_id in video_ids {
+            if let Some(progress) = statement.query_row([video_id], progress_from_row).optional()? {
+                result.insert(video_id.clone(), progress);
+            }
+        }
+        Ok(result)
+    }
+
+    /// 途中まで見た動画を最近再生した順に返す
+    pub fn continue_watching(&self, limit: usize) -> Result<Vec<ContinueWatchingEntry>> {
+        let conn = self.connection();
+        let mut statement = conn.prepare(
+            "SELECT p.video_id, p.position, p.duration, p.watched, p.updated_at,
+                    h.title, h.url, h.channel_id, h.channel_name
+             FROM playback_positions p JOIN history h ON h.video_id = p.video_id
+             WHERE p.watched = 0 AND p.position >= ?1
+             ORDER BY p.updated_at DESC LIMIT ?2",
+        )?;
+        let entries = statement
+            .query_map(params![MIN_RESUME_POSITION, limit as i64], |row| {
+                Ok(ContinueWatchingEntry {
+                    progress: progress_from_row(row)?,
+                    title: row.get(5)?,
+                    url: row.get(6)?,
+                    channel_id: row.get(7)?,
+                    channel_name: row.get(8)?,
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(entries)
+    }
+}
+
+
+// --- Tauri Commands ---
+
+/// プレイヤーから再生位置を受け取る
+///
+/// 再生中は頻繁に呼ばれるので、動画ごとに `WRITE_INTERVAL` に1回だけ書き込む。
+/// 一時停止・終了・画面を離れるときは `force` で必ず書き込む。書き込んだ場合は true。
+#[tauri::command]
+pub fn update_playback_position(
+    database: State<'_, Database>,
+    video_id: String,
+    position: f64,
+    duration: Option<f64>,
+    force: Option<bool>,
+) -> Result<bool, String> {
+    if !position.is_finite() || !should_write(&video_id, force.unwrap_or(false)) {
+        return Ok(false);
+    }
+    database.save_playback_position(&video_id, position, duration).map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+/// 続きから再生する位置 (秒)。視聴済み、または最初の方しか見ていなければ None
+#[tauri::command]
+pub fn get_resume_position(database: State<'_, Database>, video_id: String) -> Result<Option<f64>, String> {
+    let progress = database.get_playback(&video_id).map_err(|e| e.to_string())?;
+    Ok(progress.filter(|p| !p.watched && p.position >= MIN_RESUME_POSITION).map(|p| p.position))
+}
+
+#[tauri::command]
+pub fn mark_watched(database: State<'_, Database>, video_id: String, watched: Option<bool>) -> Result<(), String> {
+    database.set_watched(&video_id, watched.unwrap_or(true)).map_err(|e| e.to_string())?;
+    forget_write(&video_id);
+    Ok(())
+}
+
+/// 動画IDごとの視聴状況 (記録の無い動画は含まない)
+#[tauri::command]
+pub fn get_watch_progress(database: State<'_, Database>, video_ids: Vec<String>) -> Result<HashMap<String, WatchProgress>, String> {
+    database.watch_progress(&video_ids).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_continue_watching(database: State<'_, Database>, limit: Option<usize>) -> Result<Vec<ContinueWatchingEntry>, String> {
+    database.continue_watching(limit.unwrap_or(DEFAULT_CONTINUE_WATCHING_LIMIT)).map_err(|e| e.to_string())
+}
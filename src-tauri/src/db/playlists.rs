@@ -0,0 +1,235 @@
+use tauri::{AppHandle, Emitter, Manager, State};
+use serde::{Deserialize, Serialize};
+use rusqlite::{params, Transaction};
+use anyhow::Result;
+
+use crate::db::connection::{now_millis, Database};
+
+
+// --- 型定義 ---
+
+/// プレイリストの動画1件 (`id` は履歴と同じくチャンネルID)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaylistVideo {
+    pub title: String,
+    pub url: String,
+    pub id: String,
+    pub channel_name: String,
+}
+
+/// フロントエンドの `AppSettings.playlist[id]` と同じ形 (動画は動画IDをキーにしたオブジェクト)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Playlist {
+    pub name: String,
+    #[serde(default)]
+    pub thumbnail: String,
+    #[serde(default, with = "ordered_map")]
+    pub videos: Vec<(String, PlaylistVideo)>,
+}
+
+/// プレイリストIDをキーにしたオブジェクトとして返す (作成順)
+#[derive(Debug, Serialize)]
+pub struct PlaylistMap(#[serde(with = "ordered_map")] Vec<(String, Playlist)>);
+
+/// 取り込むプレイリスト1件 (動画は並び順を保つ)
+#[derive(Debug, Clone)]
+pub struct ImportedPlaylist {
+    pub id: String,
+    pub name: String,
+    pub videos: Vec<(String, PlaylistVideo)>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct PlaylistImportReport {
+    pub playlists: usize,
+    pub videos: usize,
+}
+
+/// JSONのオブジェクトとキーの順番を保ったまま相互に変換する
+mod ordered_map {
+    use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
+    use serde::ser::{Serialize, Serializer};
+    use std::marker::PhantomData;
+
+    pub fn serialize<V: Serialize, S: Serializer>(entries: &[(String, V)], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(entries.iter().map(|(key, value)| (key, value)))
+    }
+
+    pub fn deserialize<'de, V: Deserialize<'de>, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<(String, V)>, D::Error> {
+        struct EntriesVisitor<V>(PhantomData<V>);
+
+        impl<'de, V: Deserialize<'de>> Visitor<'de> for EntriesVisitor<V> {
+            type Value = Vec<(String, V)>;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a map")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut entries = Vec::new();
+                while let Some(entry) = map.next_entry()? {
+                    entries.push(entry);
+                }
+                Ok(entries)
+            }
+        }
+
+        deserializer.deserialize_map(EntriesVisitor(PhantomData))
+    }
+}
+
+
+// --- プライベートヘルパー関数 ---
+
+fn insert_item(tx: &Transaction, playlist_id: &str, video_id: &str, video: &PlaylistVideo) -> rusqlite::Result<usize> {
+    tx.execute(
+        "INSERT OR IGNORE INTO playlist_items (playlist_id, video_id, title, url, channel_id, channel_name, position)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, (SELECT COALESCE(MAX(position), -1) + 1 FROM playlist_items WHERE playlist_id = ?1))",
+        params![playlist_id, video_id, video.title, video.url, video.id, video.channel_name],
+    )
+}
+
+
+impl Database {
+    pub fn list_playlists(&self) -> Result<PlaylistMap> {
+        let conn = self.connection();
+        let mut playlists: Vec<(String, Playlist)> = conn
+            .prepare("SELECT id, name, thumbnail FROM playlists ORDER BY created_at, id")?
+            .query_map([], |row| {
+                Ok((row.get(0)?, Playlist { name: row.get(1)?, thumbnail: row.get(2)?, videos: Vec::new() }))
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+
+        let mut statement = conn.prepare(
+            "SELECT video_id, title, url, channel_id, channel_name FROM playlist_items
+             WHERE playlist_id = ?1 ORDER BY position",
+        )?;
+        for (id, playlist) in playlists.iter_mut() {
+            playlist.videos = statement
+                .query_map([id.as_str()], |row| {
+                    Ok((row.get(0)?, PlaylistVideo {
+                        title: row.get(1)?,
+                        url: row.get(2)?,
+                        id: row.get(3)?,
+                        channel_name: row.get(4)?,
+                    }))
+                })?
+                .collect::<rusqlite::Result<_>>()?;
+        }
+        Ok(PlaylistMap(playlists))
+    }
+
+    /// プレイリストを作成、または名前・サムネイル・動画をまとめて置き換える
+    pub fn save_playlist(&self, playlist_id: &str, playlist: &Playlist) -> Result<()> {
+        let mut conn = self.connection();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT INTO playlists (id, name, thumbnail, created_at) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(id) DO UPDATE SET name = excluded.name, thumbnail = excluded.thumbnail",
+            params![playlist_id, playlist.name, playlist.thumbnail, now_millis()],
+        )?;
+        tx.execute("DELETE FROM playlist_items WHERE playlist_id = ?1", [playlist_id])?;
+        for (video_id, video) in &playlist.videos {
+            insert_item(&tx, playlist_id, video_id, video)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn remove_playlist(&self, playlist_id: &str) -> Result<()> {
+        self.connection().execute("DELETE FROM playlists WHERE id = ?1", [playlist_id])?;
+        Ok(())
+    }
+
+    /// 末尾に追加する。既に入っている動画は内容だけ更新する
+    pub fn add_playlist_item(&self, playlist_id: &str, video_id: &str, video: &PlaylistVideo) -> Result<()> {
+        let mut conn = self.connection();
+        let tx = conn.transaction()?;
+        if insert_item(&tx, playlist_id, video_id, video)? == 0 {
+            tx.execute(
+                "UPDATE playlist_items SET title = ?3, url = ?4, channel_id = ?5, channel_name = ?6
+                 WHERE playlist_id = ?1 AND video_id = ?2",
+                params![playlist_id, video_id, video.title, video.url, video.id, video.channel_name],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn remove_playlist_item(&self, playlist_id: &str, video_id: &str) -> Result<()> {
+        self.connection().execute(
+            "DELETE FROM playlist_items WHERE playlist_id = ?1 AND video_id = ?2",
+            [playlist_id, video_id],
+        )?;
+        Ok(())
+    }
+
+    /// 取り込んだプレイリストを保存する。同じIDのプレイリストがあれば、入っていない動画だけを追加する
+    pub fn import_playlists(&self, playlists: Vec<ImportedPlaylist>) -> Result<PlaylistImportReport> {
+        let mut conn = self.connection();
+        let tx = conn.transaction()?;
+        let mut report = PlaylistImportReport::default();
+
+        for playlist in playlists {
+            let thumbnail = playlist.videos.first()
+                .map(|(video_id, _)| format!("https://i.ytimg.com/vi/{}/mqdefault.jpg", video_id))
+                .unwrap_or_default();
+            report.playlists += tx.execute(
+                "INSERT OR IGNORE INTO playlists (id, name, thumbnail, created_at) VALUES (?1, ?2, ?3, ?4)",
+                params![playlist.id, playlist.name, thumbnail, now_millis()],
+            )?;
+            for (video_id, video) in &playlist.videos {
+                report.videos += insert_item(&tx, &playlist.id, video_id, video)?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(report)
+    }
+}
+
+
+// --- 公開関数 ---
+
+/// 取り込んだプレイリストを保存し、フロントエンドに `playlists_changed` で知らせる
+pub fn merge_playlists(app_handle: &AppHandle, playlists: Vec<ImportedPlaylist>) -> Result<PlaylistImportReport> {
+    let report = app_handle.state::<Database>().import_playlists(playlists)?;
+    if report.playlists > 0 || report.videos > 0 {
+        app_handle.emit("playlists_changed", ())?;
+    }
+    Ok(report)
+}
+
+
+// --- Tauri Commands ---
+
+#[tauri::command]
+pub fn list_playlists(database: State<'_, Database>) -> Result<PlaylistMap, String> {
+    database.list_playlists().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn save_playlist(database: State<'_, Database>, playlist_id: String, playlist: Playlist) -> Result<(), String> {
+    database.save_playlist(&playlist_id, &playlist).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn remove_playlist(database: State<'_, Database>, playlist_id: String) -> Result<(), String> {
+    database.remove_playlist(&playlist_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn add_playlist_item(
+    database: State<'_, Database>,
+    playlist_id: String,
+    video_id: String,
+    video: PlaylistVideo,
+) -> Result<(), String> {
+    database.add_playlist_item(&playlist_id, &video_id, &video).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn remove_playlist_item(database: State<'_, Database>, playlist_id: String, video_id: String) -> Result<(), String> {
+    database.remove_playlist_item(&playlist_id, &video_id).map_err(|e| e.to_string())
+}
@@ -0,0 +1,317 @@
+use tauri::{AppHandle, Manager, State};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use rusqlite::{params, params_from_iter};
+use anyhow::Result;
+
+use crate::db::connection::{now_millis, Database};
+use crate::subscription::video::ChannelVideo;
+
+// --- 定数定義 ---
+const DEFAULT_LIMIT: usize = 50;
+/// trigramで引ける最短の語 (これより短い語はLIKEで探す)
+const MIN_TRIGRAM_CHARS: usize = 3;
+/// bm25の列ごとの重み (kind, video_id, channel_id, playlist_id, url, title, channel_name, description)
+const BM25_WEIGHTS: &str = "0, 0, 0, 0, 0, 10.0, 4.0, 1.0";
+
+/// 索引をすべて作り直す (VACUUMで元の表の rowid が振り直されたときなど)
+const REBUILD_SEARCH_INDEX: &str = r#"
+    DELETE FROM search_index;
+    INSERT INTO search_index (rowid, kind, video_id, channel_id, playlist_id, url, title, channel_name, description)
+    SELECT rowid * 4 + 0, 'history', video_id, channel_id, NULL, url, title, channel_name, '' FROM history;
+    INSERT INTO search_index (rowid, kind, video_id, channel_id, playlist_id, url, title, channel_name, description)
+    SELECT rowid * 4 + 1, 'playlist_item', video_id, channel_id, playlist_id, url, title, channel_name, '' FROM playlist_items;
+    INSERT INTO search_index (rowid, kind, video_id, channel_id, playlist_id, url, title, channel_name, description)
+    SELECT rowid * 4 + 2, 'channel', NULL, id, NULL, 'https://www.youtube.com/channel/' || id,
+        channel_name, COALESCE(json_extract(cache, '$.atId'), ''), COALESCE(json_extract(cache, '$.description'), '')
+    FROM channels;
+    INSERT INTO search_index (rowid, kind, video_id, channel_id, playlist_id, url, title, channel_name, description)
+    SELECT rowid * 4 + 3, 'video', video_id, channel_id, NULL, url, title, channel_name, description FROM video_metadata;
+"#;
+
+
+// --- 型定義 ---
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchKind {
+    History,
+    PlaylistItem,
+    Channel,
+    /// フィードやダウンロードで見かけた動画
+    Video,
+}
+
+impl SearchKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SearchKind::History => "history",
+            SearchKind::PlaylistItem => "playlist_item",
+            SearchKind::Channel => "channel",
+            SearchKind::Video => "video",
+        }
+    }
+
+    fn parse(kind: &str) -> Option<Self> {
+        match kind {
+            "history" => Some(SearchKind::History),
+            "playlist_item" => Some(SearchKind::PlaylistItem),
+            "channel" => Some(SearchKind::Channel),
+            "video" => Some(SearchKind::Video),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct SearchQuery {
+    pub text: String,
+    /// 空なら全種類
+    pub kinds: Vec<SearchKind>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchResult {
+    pub kind: SearchKind,
+    pub video_id: Option<String>,
+    pub channel_id: Option<String>,
+    pub playlist_id: Option<String>,
+    pub url: String,
+    pub title: String,
+    pub channel_name: String,
+    /// 一致箇所の前後 (一致部分は `[` `]` で囲む)。3文字未満の語だけのときは None
+    pub snippet: Option<String>,
+}
+
+/// 検索用に保存する動画の情報
+#[derive(Debug, Clone)]
+pub struct VideoMetadata {
+    pub video_id: String,
+    pub title: String,
+    pub description: String,
+    pub channel_id: String,
+    pub channel_name: String,
+    pub url: String,
+    pub thumbnail: Option<String>,
+}
+
+impl VideoMetadata {
+    /// yt-dlpの `.info.json` から作る
+    pub fn from_info_json(info: &Value) -> Option<Self> {
+        let video_id = info["id"].as_str()?.to_string();
+        Some(Self {
+            title: info["title"].as_str().unwrap_or("").to_string(),
+            description: info["description"].as_str().unwrap_or("").to_string(),
+            channel_id: info["channel_id"].as_str().unwrap_or("").to_string(),
+            channel_name: info["channel"].as_str().or(info["uploader"].as_str()).unwrap_or("").to_string(),
+            url: info["webpage_url"].as_str()
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("https://www.youtube.com/watch?v={}", video_id)),
+            thumbnail: info["thumbnail"].as_str().map(str::to_string),
+            video_id,
+        })
+    }
+
+    /// フロントエンドのチャンネルキャッシュ (`Channel`) に入っている動画から作る
+    pub fn from_channel_cache(cache: &Value) -> Vec<Self> {
+        let channel_id = cache["id"].as_str().unwrap_or("");
+        let channel_name = cache["name"].as_str().unwrap_or("");
+        let Some(videos) = cache["videos"].as_array() else { return Vec::new() };
+
+        videos.iter()
+            .filter_map(|video| {
+                Some(Self {
+                    video_id: video["id"].as_str()?.to_string(),
+                    title: video["title"].as_str().unwrap_or("").to_string(),
+                    description: String::new(),
+                    channel_id: channel_id.to_string(),
+                    channel_name: channel_name.to_string(),
+                    url: video["url"].as_str().unwrap_or("").to_string(),
+                    thumbnail: video["thumbnail"].as_str().map(str::to_string),
+                })
+            })
+            .collect()
+    }
+}
+
+impl From<&ChannelVideo> for VideoMetadata {
+    fn from(video: &ChannelVideo) -> Self {
+        Self {
+            video_id: video.video_id.clone(),
+            title: video.title.clone(),
+            description: String::new(),
+            channel_id: video.channel_id.clone(),
+            channel_name: video.channel_name.clone().unwrap_or_default(),
+            url: video.url.clone(),
+            thumbnail: video.thumbnail.clone(),
+        }
+    }
+}
+
+
+// --- プライベートヘルパー関数 ---
+
+/// FTS5のフレーズとして扱えるように `"` で囲む
+fn quote_phrase(term: &str) -> String {
+    format!("\"{}\"", term.replace('"', "\"\""))
+}
+
+/// LIKEの `%` `_` をエスケープする (ESCAPE '\')
+fn escape_like(term: &str) -> String {
+    term.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+/// 検索語を、trigramで探す語とLIKEで探す短い語に分ける
+fn split_terms(text: &str) -> (Vec<String>, Vec<String>) {
+    text.split_whitespace()
+        .map(|term| term.trim_end_matches('*'))
+        .filter(|term| !term.is_empty())
+        .map(str::to_string)
+        .partition(|term| term.chars().count() >= MIN_TRIGRAM_CHARS)
+}
+
+
+impl Database {
+    /// 見かけた動画の情報を保存する。説明が空のときは保存済みの説明を残す
+    pub fn upsert_video_metadata(&self, videos: &[VideoMetadata]) -> Result<()> {
+        let mut conn = self.connection();
+        let tx = conn.transaction()?;
+        {
+            let mut statement = tx.prepare(
+                "INSERT INTO video_metadata (video_id, title, description, channel_id, channel_name, url, thumbnail, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+                 ON CONFLICT(video_id) DO UPDATE SET
+                    title = excluded.title,
+                    description = CASE WHEN excluded.description = '' THEN video_metadata.description ELSE excluded.description END,
+                    channel_id = CASE WHEN excluded.channel_id = '' THEN video_metadata.channel_id ELSE excluded.channel_id END,
+                    channel_name = CASE WHEN excluded.channel_name = '' THEN video_metadata.channel_name ELSE excluded.channel_name END,
+                    url = excluded.url,
+                    thumbnail = COALESCE(excluded.thumbnail, video_metadata.thumbnail),
+                    updated_at = excluded.updated_at",
+            )?;
+            for video in videos.iter().filter(|v| !v.video_id.is_empty() && !v.title.is_empty()) {
+                statement.execute(params![
+                    video.video_id,
+                    video.title,
+                    video.description,
+                    video.channel_id,
+                    video.channel_name,
+                    video.url,
+                    video.thumbnail,
+                    now_millis(),
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn rebuild_search_index(&self) -> Result<()> {
+        let mut conn = self.connection();
+        let tx = conn.transaction()?;
+        tx.execute_batch(REBUILD_SEARCH_INDEX)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// タイトル・チャンネル名・説明から探す
+    ///
+    /// 3文字以上の語はtrigramの索引で探してbm25で並べ、タイトルが検索語で始まるものを先にする。
+    /// 2文字以下の語はLIKEで絞り込む。
+    pub fn search(&self, query: &SearchQuery) -> Result<Vec<SearchResult>> {
+        let (long_terms, short_terms) = split_terms(&query.text);
+        if long_terms.is_empty() && short_terms.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut conditions = Vec::new();
+        let mut values: Vec<String> = Vec::new();
+
+        let has_match = !long_terms.is_empty();
+        if has_match {
+            values.push(long_terms.iter().map(|t| quote_phrase(t)).collect::<Vec<_>>().join(" "));
+            conditions.push(format!("search_index MATCH ?{}", values.len()));
+        }
+        for term in &short_terms {
+            values.push(format!("%{}%", escape_like(term)));
+            let n = values.len();
+            conditions.push(format!(
+                "(title LIKE ?{n} ESCAPE '\\' OR channel_name LIKE ?{n} ESCAPE '\\' OR description LIKE ?{n} ESCAPE '\\')"
+            ));
+        }
+        if !query.kinds.is_empty() {
+            let kinds = query.kinds.iter().map(|k| format!("'{}'", k.as_str())).collect::<Vec<_>>().join(", ");
+            conditions.push(format!("kind IN ({})", kinds));
+        }
+
+        let first_term = long_terms.first().or(short_terms.first()).map(String::as_str).unwrap_or_default();
+        values.push(format!("{}%", escape_like(first_term)));
+        let prefix_param = values.len();
+
+        let (snippet, score) = if has_match {
+            (
+                "snippet(search_index, -1, '[', ']', '…', 16)".to_string(),
+                format!("bm25(search_index, {})", BM25_WEIGHTS),
+            )
+        } else {
+            ("NULL".to_string(), "0".to_string())
+        };
+
+        let sql = format!(
+            "SELECT kind, video_id, channel_id, playlist_id, url, title, channel_name, {snippet}
+             FROM search_index WHERE {conditions}
+             ORDER BY (title LIKE ?{prefix_param} ESCAPE '\\') DESC, {score}, rowid DESC
+             LIMIT {limit}",
+            conditions = conditions.join(" AND "),
+            limit = query.limit.unwrap_or(DEFAULT_LIMIT),
+        );
+
+        let conn = self.connection();
+        let mut statement = conn.prepare(&sql)?;
+        let results = statement
+            .query_map(params_from_iter(values.iter()), |row| {
+                let kind: String = row.get(0)?;
+                let Some(kind) = SearchKind::parse(&kind) else { return Ok(None) };
+                Ok(Some(SearchResult {
+                    kind,
+                    video_id: row.get(1)?,
+                    channel_id: row.get(2)?,
+                    playlist_id: row.get(3)?,
+                    url: row.get(4)?,
+                    title: row.get(5)?,
+                    channel_name: row.get(6)?,
+                    snippet: row.get(7)?,
+                }))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(results.into_iter().flatten().collect())
+    }
+}
+
+
+// --- 公開関数 ---
+
+/// 見かけた動画の情報を検索用に保存する (失敗してもログだけ残す)
+pub fn record_video_metadata(app_handle: &AppHandle, videos: &[VideoMetadata]) {
+    if videos.is_empty() {
+        return;
+    }
+    if let Err(e) = app_handle.state::<Database>().upsert_video_metadata(videos) {
+        println!("Failed to save video metadata: {}", e);
+    }
+}
+
+
+// --- Tauri Commands ---
+
+#[tauri::command]
+pub fn search_local(database: State<'_, Database>, query: SearchQuery) -> Result<Vec<SearchResult>, String> {
+    database.search(&query).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn rebuild_search_index(database: State<'_, Database>) -> Result<(), String> {
+    database.rebuild_search_index().map_err(|e| e.to_string())
+}
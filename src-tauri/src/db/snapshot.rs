@@ -0,0 +1,127 @@
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use rusqlite::Connection;
+use anyhow::Result;
+
+use crate::db::connection::Database;
+use crate::db::migrations;
+
+// --- 定数定義 ---
+/// 復元時に ATTACH するスキーマ名
+const ATTACHED_SCHEMA: &str = "restore_source";
+
+/// 置き換えのときに先に消す表 (playlist_items は playlists から連鎖して消える)
+const REPLACE_SQL: &str = r#"
+    DELETE FROM main.channels;
+    DELETE FROM main.playlists;
+    DELETE FROM main.history;
+    DELETE FROM main.playback_positions;
+    DELETE FROM main.video_metadata;
+"#;
+
+/// 表ごとの取り込み。既にある行は残し、フォローは今の並びの後ろに付ける
+const MERGE_CHANNELS_SQL: &str = r#"
+    INSERT OR IGNORE INTO main.channels (id, channel_name, cache, position, followed_at)
+    SELECT id, channel_name, cache, (SELECT COALESCE(MAX(position), -1) + 1 FROM main.channels) + position, followed_at
+    FROM restore_source.channels ORDER BY position
+"#;
+const MERGE_PLAYLISTS_SQL: &str = r#"
+    INSERT OR IGNORE INTO main.playlists (id, name, thumbnail, created_at)
+    SELECT id, name, thumbnail, created_at FROM restore_source.playlists
+"#;
+const MERGE_PLAYLIST_ITEMS_SQL: &str = r#"
+    INSERT OR IGNORE INTO main.playlist_items (playlist_id, video_id, title, url, channel_id, channel_name, position)
+    SELECT playlist_id, video_id, title, url, channel_id, channel_name,
+        (SELECT COALESCE(MAX(m.position), -1) + 1 FROM main.playlist_items m WHERE m.playlist_id = s.playlist_id) + position
+    FROM restore_source.playlist_items s ORDER BY playlist_id, position
+"#;
+/// 同じ動画は新しい視聴日時を残す
+const MERGE_HISTORY_SQL: &str = r#"
+    INSERT INTO main.history (key, video_id, title, url, channel_id, channel_name, watched_at)
+    SELECT key, video_id, title, url, channel_id, channel_name, watched_at FROM restore_source.history WHERE true
+    ON CONFLICT(key) DO UPDATE SET watched_at = excluded.watched_at WHERE excluded.watched_at > history.watched_at
+"#;
+/// 同じ動画は新しく更新された方を残す
+const MERGE_PLAYBACK_SQL: &str = r#"
+    INSERT INTO main.playback_positions (video_id, position, duration, watched, updated_at)
+    SELECT video_id, position, duration, watched, updated_at FROM restore_source.playback_positions WHERE true
+    ON CONFLICT(video_id) DO UPDATE SET
+        position = excluded.position, duration = excluded.duration,
+        watched = excluded.watched, updated_at = excluded.updated_at
+    WHERE excluded.updated_at > playback_positions.updated_at
+"#;
+const MERGE_VIDEO_METADATA_SQL: &str = r#"
+    INSERT OR IGNORE INTO main.video_metadata (video_id, title, description, channel_id, channel_name, url, thumbnail, updated_at)
+    SELECT video_id, title, description, channel_id, channel_name, url, thumbnail, updated_at FROM restore_source.video_metadata
+"#;
+
+
+// --- 型定義 ---
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RestoreMode {
+    /// 今のデータを残し、無いものだけを加える
+    Merge,
+    /// 今のデータを消してバックアップの内容にする
+    Replace,
+}
+
+/// 表ごとの追加・更新した行数
+#[derive(Debug, Default, Serialize)]
+pub struct RestoreCounts {
+    pub subscriptions: usize,
+    pub playlists: usize,
+    pub playlist_items: usize,
+    pub history: usize,
+    pub playback_positions: usize,
+    pub video_metadata: usize,
+}
+
+
+// --- 公開関数 ---
+
+/// 別ファイルのデータベースを今のスキーマまで上げる (古いバックアップの復元用)
+///
+/// 元のスキーマのバージョンを返す。
+pub fn upgrade_database_file(path: &Path) -> Result<usize> {
+    let mut conn = Connection::open(path)?;
+    let version = migrations::schema_version(&conn)?;
+    migrations::migrate(&mut conn)?;
+    Ok(version)
+}
+
+
+impl Database {
+    /// 書き込み中でも一貫した状態のコピーを `path` に作る (`path` は存在しないこと)
+    pub fn snapshot_to(&self, path: &Path) -> Result<()> {
+        self.connection().execute("VACUUM INTO ?1", [path.to_string_lossy()])?;
+        Ok(())
+    }
+
+    /// 今のスキーマまで上げたデータベースファイルから取り込む
+    pub fn restore_from(&self, path: &Path, mode: RestoreMode) -> Result<RestoreCounts> {
+        let mut conn = self.connection();
+        conn.execute(&format!("ATTACH DATABASE ?1 AS {}", ATTACHED_SCHEMA), [path.to_string_lossy()])?;
+
+        let result = (|| -> Result<RestoreCounts> {
+            let tx = conn.transaction()?;
+            if mode == RestoreMode::Replace {
+                tx.execute_batch(REPLACE_SQL)?;
+            }
+            let counts = RestoreCounts {
+                subscriptions: tx.execute(MERGE_CHANNELS_SQL, [])?,
+                playlists: tx.execute(MERGE_PLAYLISTS_SQL, [])?,
+                playlist_items: tx.execute(MERGE_PLAYLIST_ITEMS_SQL, [])?,
+                history: tx.execute(MERGE_HISTORY_SQL, [])?,
+                playback_positions: tx.execute(MERGE_PLAYBACK_SQL, [])?,
+                video_metadata: tx.execute(MERGE_VIDEO_METADATA_SQL, [])?,
+            };
+            tx.commit()?;
+            Ok(counts)
+        })();
+
+        conn.execute(&format!("DETACH DATABASE {}", ATTACHED_SCHEMA), [])?;
+        result
+    }
+}
@@ -0,0 +1,137 @@
+use tauri::{AppHandle, Manager, State};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use rusqlite::params;
+use anyhow::Result;
+
+use crate::db::connection::{now_millis, Database};
+use crate::db::search::VideoMetadata;
+
+
+// --- 型定義 ---
+
+/// フォロー中のチャンネル (フロントエンドの `AppSettings.followChannel` の1件)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FollowedChannel {
+    pub id: String,
+    pub channel_name: String,
+    /// フロントエンドが保存するチャンネル情報のキャッシュ
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache: Option<Value>,
+}
+
+
+impl Database {
+    /// 並び順どおりに返す
+    pub fn list_subscriptions(&self) -> Result<Vec<FollowedChannel>> {
+        let conn = self.connection();
+        let mut statement = conn.prepare("SELECT id, channel_name, cache FROM channels ORDER BY position")?;
+        let channels = statement
+            .query_map([], |row| {
+                let cache: Option<String> = row.get(2)?;
+                Ok(FollowedChannel {
+                    id: row.get(0)?,
+                    channel_name: row.get(1)?,
+                    cache: cache.and_then(|c| serde_json::from_str(&c).ok()),
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(channels)
+    }
+
+    /// 末尾に追加する (既にあるものは除く)。追加した件数を返す
+    pub fn add_subscriptions(&self, channels: &[FollowedChannel]) -> Result<usize> {
+        let mut conn = self.connection();
+        let tx = conn.transaction()?;
+        let mut added = 0;
+        {
+            let mut statement = tx.prepare(
+                "INSERT OR IGNORE INTO channels (id, channel_name, cache, position, followed_at)
+                 VALUES (?1, ?2, ?3, (SELECT COALESCE(MAX(position), -1) + 1 FROM channels), ?4)",
+            )?;
+            for channel in channels {
+                let cache = channel.cache.as_ref().map(Value::to_string);
+                added += statement.execute(params![channel.id, channel.channel_name, cache, now_millis()])?;
+            }
+        }
+        tx.commit()?;
+        Ok(added)
+    }
+
+    pub fn remove_subscription(&self, channel_id: &str) -> Result<()> {
+        self.connection().execute("DELETE FROM channels WHERE id = ?1", [channel_id])?;
+        Ok(())
+    }
+
+    /// キャッシュに入っている動画は検索用にも保存する
+    pub fn set_subscription_cache(&self, channel_id: &str, cache: &Value) -> Result<()> {
+        self.connection().execute(
+            "UPDATE channels SET cache = ?2 WHERE id = ?1",
+            params![channel_id, cache.to_string()],
+        )?;
+        self.upsert_video_metadata(&VideoMetadata::from_channel_cache(cache))
+    }
+
+    /// チャンネルを `index` 番目に移動し、並び順を振り直す
+    pub fn move_subscription(&self, channel_id: &str, index: usize) -> Result<()> {
+        let mut conn = self.connection();
+        let tx = conn.transaction()?;
+        let mut ids: Vec<String> = tx.prepare("SELECT id FROM channels ORDER BY position")?
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+
+        let Some(current) = ids.iter().position(|id| id == channel_id) else { return Ok(()) };
+        let id = ids.remove(current);
+        ids.insert(index.min(ids.len()), id);
+
+        for (position, id) in ids.iter().enumerate() {
+            tx.execute("UPDATE channels SET position = ?2 WHERE id = ?1", params![id, position as i64])?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+
+// --- 公開関数 ---
+
+/// フォロー中のチャンネル一覧を取得する
+pub fn get_followed_channels(app_handle: &AppHandle) -> Result<Vec<FollowedChannel>> {
+    app_handle.state::<Database>().list_subscriptions()
+}
+
+/// フォロー中のチャンネルを追加する (既にあるものは除く)。追加した件数を返す
+pub fn add_followed_channels(app_handle: &AppHandle, channels: &[FollowedChannel]) -> Result<usize> {
+    app_handle.state::<Database>().add_subscriptions(channels)
+}
+
+
+// --- Tauri Commands ---
+
+#[tauri::command]
+pub fn list_subscriptions(database: State<'_, Database>) -> Result<Vec<FollowedChannel>, String> {
+    database.list_subscriptions().map_err(|e| e.to_string())
+}
+
+/// 追加した場合は true (既にフォロー済みなら false)
+#[tauri::command]
+pub fn add_subscription(database: State<'_, Database>, channel_id: String, channel_name: String) -> Result<bool, String> {
+    let channel = FollowedChannel { id: channel_id, channel_name, cache: None };
+    database.add_subscriptions(&[channel]).map(|added| added > 0).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn remove_subscription(database: State<'_, Database>, channel_id: String) -> Result<(), String> {
+    database.remove_subscription(&channel_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn set_subscription_cache(database: State<'_, Database>, channel_id: String, cache: Value) -> Result<(), String> {
+    database.set_subscription_cache(&channel_id, &cache).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn move_subscription(database: State<'_, Database>, channel_id: String, index: usize) -> Result<(), String> {
+    database.move_subscription(&channel_id, index).map_err(|e| e.to_string())
+}
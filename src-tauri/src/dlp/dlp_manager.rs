@@ -1,19 +1,73 @@
-use tauri::{AppHandle, Manager, Emitter};
+use tauri::{AppHandle, Manager, Emitter, State};
 use std::path::{Path, PathBuf};
-use serde::Deserialize;
-use anyhow::{Result, Context};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::{Notify, OwnedRwLockReadGuard, OwnedRwLockWriteGuard, RwLock};
+use serde::{Deserialize, Serialize};
+use anyhow::{Result, Context, anyhow};
 use sha2::{Sha256, Digest};
-use std::io::Write;
 
-// --- 定数定義 ---
-const YTDLP_REPO: &str = "yt-dlp/yt-dlp";
+use crate::dlp::signature::TrustedKeys;
+use crate::dlp::transfer::{download_resumable, TransferProgress, CONNECT_TIMEOUT};
+use crate::dlp::version::{UpdateChannel, YtDlpVersion};
+use crate::download::manager::DownloadManager;
 
+// --- 定数定義 ---
 #[cfg(target_os = "windows")]
 const YTDLP_EXECUTABLE_NAME: &str = "yt-dlp.exe";
 #[cfg(not(target_os = "windows"))]
 const YTDLP_EXECUTABLE_NAME: &str = "yt-dlp";
 
+/// リリースのアセットのうち、このOS・アーキテクチャで使えるものを優先順に並べる
+///
+/// 単体で動くビルドを優先し、最後にPythonが必要な zipapp (`yt-dlp`) を試す。
+#[cfg(all(target_os = "windows", target_arch = "x86_64"))]
+const YTDLP_ASSET_CANDIDATES: &[&str] = &["yt-dlp.exe", "yt-dlp_x86.exe"];
+#[cfg(all(target_os = "windows", target_arch = "x86"))]
+const YTDLP_ASSET_CANDIDATES: &[&str] = &["yt-dlp_x86.exe"];
+#[cfg(all(target_os = "windows", target_arch = "aarch64"))]
+const YTDLP_ASSET_CANDIDATES: &[&str] = &["yt-dlp_arm64.exe", "yt-dlp_x86.exe"];
+#[cfg(target_os = "macos")]
+const YTDLP_ASSET_CANDIDATES: &[&str] = &["yt-dlp_macos", "yt-dlp_macos_legacy", "yt-dlp"];
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+const YTDLP_ASSET_CANDIDATES: &[&str] = &["yt-dlp_linux", "yt-dlp"];
+#[cfg(all(target_os = "linux", target_arch = "aarch64"))]
+const YTDLP_ASSET_CANDIDATES: &[&str] = &["yt-dlp_linux_aarch64", "yt-dlp"];
+#[cfg(all(target_os = "linux", target_arch = "arm"))]
+const YTDLP_ASSET_CANDIDATES: &[&str] = &["yt-dlp_linux_armv7l", "yt-dlp"];
+#[cfg(not(any(
+    all(target_os = "windows", any(target_arch = "x86_64", target_arch = "x86", target_arch = "aarch64")),
+    target_os = "macos",
+    all(target_os = "linux", any(target_arch = "x86_64", target_arch = "aarch64", target_arch = "arm")),
+)))]
+const YTDLP_ASSET_CANDIDATES: &[&str] = &["yt-dlp"];
+
 const VERSION_FILE_NAME: &str = ".ytdlp-version";
+const SETTINGS_FILE_NAME: &str = "ytdlp-updater.json";
+/// ひとつ前のバージョンの実行ファイル・バージョンファイルに付ける拡張子
+const PREVIOUS_SUFFIX: &str = "previous";
+/// ダウンロード中のファイルを置くディレクトリ (renameで入れ替えられるよう実行ファイルと同じ場所に作る)
+const STAGING_DIR_NAME: &str = ".ytdlp-staging";
+const DEFAULT_API_BASE_URL: &str = "https://api.github.com";
+/// 設定に無いときに使うトークンの環境変数
+const GITHUB_TOKEN_ENV: &str = "GITHUB_TOKEN";
+const HASHES_FILE_NAME: &str = "SHA2-256SUMS";
+/// `SHA2-256SUMS` の分離署名
+const SIGNATURE_FILE_NAME: &str = "SHA2-256SUMS.sig";
+/// 手元のファイルからインストールするときの作業ディレクトリ名
+const LOCAL_STAGING_NAME: &str = "local";
+/// 定期確認の最後の結果
+const CHECK_STATE_FILE_NAME: &str = "ytdlp-update-check.json";
+/// 起動直後はフロントエンドの起動時の更新と重ならないように待つ
+const CHECK_STARTUP_DELAY: Duration = Duration::from_secs(60);
+/// 抽出エラーで更新を試すのはこの間隔に1回まで (最新でも直っていない場合に繰り返さない)
+const EXTRACTOR_UPDATE_COOLDOWN: Duration = Duration::from_secs(60 * 60);
+/// ダウンロード中の `ytdlp_status` の進捗の範囲
+const DOWNLOAD_PROGRESS_START: u32 = 10;
+const DOWNLOAD_PROGRESS_END: u32 = 80;
+/// 実行中のyt-dlpが終わるのを待つ間、入れ替えを試す間隔
+const SWAP_RETRY_INTERVAL: Duration = Duration::from_millis(500);
 
 // --- GitHub API レスポンス用構造体 ---
 #[derive(Deserialize, Debug, Clone)]
@@ -26,6 +80,10 @@ pub struct GitHubAsset {
 pub struct GitHubRelease {
     tag_name: String,
     assets: Vec<GitHubAsset>,
+    /// リリースノート (Markdown)
+    body: Option<String>,
+    html_url: Option<String>,
+    published_at: Option<String>,
 }
 
 #[derive(Deserialize, Debug, Clone, serde::Serialize)]
@@ -34,43 +92,200 @@ struct EmitterData {
     progress: u32,
 }
 
+/// 更新の設定
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UpdaterSettings {
+    pub channel: UpdateChannel,
+    /// 指定したタグのバージョンに固定する (新しくても古くてもこのバージョンにする)
+    pub pinned_version: Option<String>,
+    /// GitHub互換のリリースAPIのベースURL (社内ミラーなど)。無ければ api.github.com
+    pub api_base_url: Option<String>,
+    /// 取得するリポジトリ ("owner/name")。無ければ系統ごとの既定のリポジトリ
+    pub repo: Option<String>,
+    /// APIの回数制限を緩めるためのトークン。無ければ環境変数 `GITHUB_TOKEN` を使う
+    pub github_token: Option<String>,
+    /// 組み込みのyt-dlpの鍵に加えて信頼する公開鍵のファイル (自前のミラーで署名し直す場合など)
+    pub signing_key_path: Option<String>,
+    /// バックグラウンドで更新を確認する間隔 (時間)。無ければ確認しない
+    pub check_interval_hours: Option<u64>,
+    /// バックグラウンドの確認で見つかった更新をそのままインストールする
+    pub auto_install: bool,
+    /// 抽出に失敗したときに更新してからやり直す
+    pub update_on_extractor_error: bool,
+}
+
+impl Default for UpdaterSettings {
+    fn default() -> Self {
+        Self {
+            channel: UpdateChannel::default(),
+            pinned_version: None,
+            api_base_url: None,
+            repo: None,
+            github_token: None,
+            signing_key_path: None,
+            check_interval_hours: None,
+            auto_install: false,
+            update_on_extractor_error: true,
+        }
+    }
+}
+
+impl UpdaterSettings {
+    fn api_base_url(&self) -> &str {
+        self.api_base_url.as_deref()
+            .map(str::trim)
+            .filter(|url| !url.is_empty())
+            .unwrap_or(DEFAULT_API_BASE_URL)
+            .trim_end_matches('/')
+    }
+
+    fn repo(&self) -> &str {
+        self.repo.as_deref()
+            .map(str::trim)
+            .filter(|repo| !repo.is_empty())
+            .unwrap_or_else(|| self.channel.repo())
+    }
+
+    fn github_token(&self) -> Option<String> {
+        self.github_token.clone()
+            .or_else(|| std::env::var(GITHUB_TOKEN_ENV).ok())
+            .map(|token| token.trim().to_string())
+            .filter(|token| !token.is_empty())
+    }
+}
+
+/// インストール済みのバージョン
+#[derive(Debug, Clone, Serialize)]
+pub struct InstalledVersions {
+    pub current: Option<String>,
+    /// ロールバックで戻せるバージョン
+    pub previous: Option<String>,
+}
+
+/// インストールせずに確認した結果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateInfo {
+    pub current: Option<String>,
+    /// 設定の系統の最新、または固定したバージョン
+    pub latest: String,
+    pub update_available: bool,
+    pub pinned: bool,
+    pub release_notes: Option<String>,
+    pub release_url: Option<String>,
+    pub published_at: Option<String>,
+    /// 秒
+    pub checked_at: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct CheckState {
+    last_checked_at: Option<u64>,
+    last_result: Option<UpdateInfo>,
+}
+
+/// yt-dlpの実行ファイルの更新を管理する
+pub struct YtDlpManager {
+    app_data_dir: PathBuf,
+    settings: Mutex<UpdaterSettings>,
+    check_state: Mutex<CheckState>,
+    /// yt-dlpを実行している間は読み取り、実行ファイルを入れ替える間は書き込みでロックする
+    ///
+    /// 書き込みを待たせると後から来た読み取りも止まるため、書き込みは空いたときに取るだけにする。
+    executable_lock: Arc<RwLock<()>>,
+    /// 入れ替えを待っている間は新しいダウンロードを始めない
+    swap_pending: AtomicBool,
+    /// 更新の確認・インストールを同時に走らせない
+    update_lock: tokio::sync::Mutex<()>,
+    /// 抽出エラーで最後に更新を試した時刻
+    last_extractor_update: Mutex<Option<Instant>>,
+    /// 設定変更時に定期確認のタイマーを作り直す
+    wake: Notify,
+}
+
 
 // --- プライベートヘルパー関数 ---
 
-/// yt-dlpの実行ファイルパスを取得
-fn get_executable_path(app_handle: &AppHandle) -> Result<PathBuf> {
-    let app_data_dir = app_handle.path().app_data_dir().context("アプリデータディレクトリの取得に失敗")?;
-    Ok(app_data_dir.join(YTDLP_EXECUTABLE_NAME))
+fn with_previous_suffix(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}", PREVIOUS_SUFFIX));
+    path.with_file_name(name)
 }
 
-/// ローカルに保存されているバージョン情報を取得
-fn get_local_version(app_handle: &AppHandle) -> Result<String> {
-    let version_file_path = app_handle.path().app_data_dir().context("アプリデータディレクトリの取得に失敗")?.join(VERSION_FILE_NAME);
-    if version_file_path.exists() {
-        Ok(std::fs::read_to_string(version_file_path)?)
-    } else {
-        Ok("0.0.0".to_string())
+/// 今のファイルを `.previous` として残す
+///
+/// Windows以外ではハードリンクにして、入れ替えが終わるまで元のパスにもファイルがあるようにする。
+fn keep_as_previous(current: &Path, previous: &Path) -> Result<()> {
+    let _ = std::fs::remove_file(previous);
+    #[cfg(not(target_os = "windows"))]
+    if std::fs::hard_link(current, previous).is_ok() {
+        return Ok(());
     }
+    std::fs::rename(current, previous)?;
+    Ok(())
 }
 
-/// 最新リリース情報をGitHub APIから取得
-async fn get_latest_release(http_client: &reqwest::Client) -> Result<GitHubRelease> {
-    let url = format!("https://api.github.com/repos/{}/releases/latest", YTDLP_REPO);
-    let release = http_client.get(&url)
+fn read_json<T: serde::de::DeserializeOwned + Default>(path: &Path) -> T {
+    std::fs::read_to_string(path).ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn read_version_file(path: &Path) -> Option<String> {
+    std::fs::read_to_string(path).ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// リリース情報を設定のAPIから取得する (`tag` が無ければ最新)
+///
+/// トークンはAPIへの問い合わせにだけ付け、アセットのダウンロードには付けない。
+async fn get_release(http_client: &reqwest::Client, settings: &UpdaterSettings, tag: Option<&str>) -> Result<GitHubRelease> {
+    let base = settings.api_base_url();
+    let repo = settings.repo();
+    let url = match tag {
+        Some(tag) => format!("{}/repos/{}/releases/tags/{}", base, repo, tag),
+        None => format!("{}/repos/{}/releases/latest", base, repo),
+    };
+    let mut request = http_client.get(&url)
         .header("User-Agent", "Tauri-YTDLP-App")
+        .header("Accept", "application/vnd.github+json");
+    if let Some(token) = settings.github_token() {
+        request = request.bearer_auth(token);
+    }
+    let release = request
         .send()
         .await?
+        .error_for_status()
+        .with_context(|| format!("リリース情報の取得に失敗 ({})", url))?
         .json::<GitHubRelease>()
         .await?;
     Ok(release)
 }
 
-/// ファイルをダウンロードする
-async fn download_file(http_client: &reqwest::Client, url: &str, path: &Path) -> Result<()> {
-    let response = http_client.get(url).send().await?.bytes().await?;
-    let mut file = std::fs::File::create(path)?;
-    file.write_all(&response)?;
-    Ok(())
+fn format_megabytes(bytes: u64) -> String {
+    format!("{:.1} MB", bytes as f64 / 1_000_000.0)
+}
+
+/// ファイルをダウンロードし、進捗を `ytdlp_download_progress` と `ytdlp_status` で知らせる
+async fn download_file(app_handle: &AppHandle, http_client: &reqwest::Client, url: &str, path: &Path) -> Result<()> {
+    download_resumable(http_client, url, path, |progress: &TransferProgress| {
+        let _ = app_handle.emit("ytdlp_download_progress", progress);
+
+        let fraction = progress.total_bytes
+            .filter(|t| *t > 0)
+            .map(|t| progress.downloaded_bytes as f64 / t as f64)
+            .unwrap_or(0.0);
+        let percent = DOWNLOAD_PROGRESS_START + ((DOWNLOAD_PROGRESS_END - DOWNLOAD_PROGRESS_START) as f64 * fraction) as u32;
+        let size = match progress.total_bytes {
+            Some(total) => format!("{} / {}", format_megabytes(progress.downloaded_bytes), format_megabytes(total)),
+            None => format_megabytes(progress.downloaded_bytes),
+        };
+        let status = format!("{} をダウンロード中... {} ({}/s)", progress.file_name, size, format_megabytes(progress.bytes_per_second));
+        let _ = emit_status(app_handle, status, percent);
+    })
+    .await
 }
 
 /// ファイルのSHA256ハッシュを計算する
@@ -83,94 +298,598 @@ fn calculate_sha256(path: &Path) -> Result<String> {
 }
 
 /// ハッシュファイルの中から特定のファイル名に対応するハッシュ値を取得
+///
+/// 行は `<ハッシュ>  <ファイル名>` (バイナリモードなら名前の前に `*`) で、名前が完全に一致するものだけを見る。
+/// `yt-dlp` で `yt-dlp_linux` などに当たらないようにするため。
 fn find_hash_for_file(hashes_content: &str, filename: &str) -> Option<String> {
-    hashes_content.lines()
-        .find(|line| line.ends_with(filename))
-        .and_then(|line| line.split_whitespace().next().map(str::to_string))
+    hashes_content.lines().find_map(|line| {
+        let (hash, name) = line.trim().split_once(char::is_whitespace)?;
+        let name = name.trim_start();
+        (name.strip_prefix('*').unwrap_or(name) == filename).then(|| hash.to_lowercase())
+    })
+}
+
+/// リリースから使うアセットを候補の順に選ぶ
+fn select_asset(assets: &[GitHubAsset]) -> Option<&GitHubAsset> {
+    YTDLP_ASSET_CANDIDATES.iter()
+        .find_map(|name| assets.iter().find(|a| a.name == *name))
+}
+
+/// インストール済みのバージョンのままでよいか
+///
+/// 固定しているときは一致しているかどうか、そうでなければ最新以上かどうかで判断する。
+fn is_up_to_date(installed: Option<&str>, target_tag: &str, pinned: bool) -> Result<bool> {
+    let Some(installed) = installed else { return Ok(false) };
+    if pinned {
+        return Ok(installed == target_tag);
+    }
+    let target = YtDlpVersion::parse(target_tag)
+        .ok_or_else(|| anyhow!("バージョンの形式が不明です: {}", target_tag))?;
+    // 読めないバージョンファイルは入れ直す
+    Ok(YtDlpVersion::parse(installed).is_some_and(|installed| installed >= target))
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+fn emit_status(app_handle: &AppHandle, status: String, progress: u32) -> Result<()> {
+    app_handle.emit("ytdlp_status", EmitterData { status, progress })?;
+    Ok(())
+}
+
+
+impl YtDlpManager {
+    pub fn new(app_handle: &AppHandle) -> Result<Self> {
+        let app_data_dir = app_handle.path().app_data_dir().context("アプリデータディレクトリの取得に失敗")?;
+        std::fs::create_dir_all(&app_data_dir)?;
+        let settings = read_json(&app_data_dir.join(SETTINGS_FILE_NAME));
+        let check_state = read_json(&app_data_dir.join(CHECK_STATE_FILE_NAME));
+        Ok(Self {
+            app_data_dir,
+            settings: Mutex::new(settings),
+            check_state: Mutex::new(check_state),
+            executable_lock: Arc::new(RwLock::new(())),
+            swap_pending: AtomicBool::new(false),
+            update_lock: tokio::sync::Mutex::new(()),
+            last_extractor_update: Mutex::new(None),
+            wake: Notify::new(),
+        })
+    }
+
+    /// 定期確認のループを開始する
+    pub fn start(app_handle: &AppHandle) {
+        let app_handle = app_handle.clone();
+        tauri::async_runtime::spawn(async move {
+            let manager = app_handle.state::<YtDlpManager>();
+            tokio::time::sleep(CHECK_STARTUP_DELAY).await;
+
+            loop {
+                let Some(wait) = manager.time_until_next_check() else {
+                    // 無効の間は設定が変わるまで待つ
+                    manager.wake.notified().await;
+                    continue;
+                };
+
+                tokio::select! {
+                    _ = tokio::time::sleep(wait) => {
+                        if let Err(e) = run_background_check(&app_handle, &manager).await {
+                            println!("Failed to check for yt-dlp updates: {}", e);
+                        }
+                    }
+                    _ = manager.wake.notified() => {}
+                }
+            }
+        });
+    }
+
+    fn time_until_next_check(&self) -> Option<Duration> {
+        let hours = self.settings().check_interval_hours.filter(|h| *h > 0)?;
+        let last = self.check_state.lock().unwrap().last_checked_at.unwrap_or(0);
+        let next = last + hours * 60 * 60;
+        Some(Duration::from_secs(next.saturating_sub(now_secs())))
+    }
+
+    /// 確認の結果を覚えておく (失敗したときも時刻は進めて、次の間隔まで待つ)
+    fn record_check(&self, result: Option<&UpdateInfo>) -> Result<()> {
+        let mut state = self.check_state.lock().unwrap();
+        state.last_checked_at = Some(now_secs());
+        if let Some(result) = result {
+            state.last_result = Some(result.clone());
+        }
+        let path = self.app_data_dir.join(CHECK_STATE_FILE_NAME);
+        let temp_path = path.with_extension("json.tmp");
+        std::fs::write(&temp_path, serde_json::to_string_pretty(&*state)?)?;
+        std::fs::rename(&temp_path, &path)?;
+        Ok(())
+    }
+
+    /// 最後に確認した結果
+    pub fn last_check(&self) -> Option<UpdateInfo> {
+        self.check_state.lock().unwrap().last_result.clone()
+    }
+
+    /// yt-dlpを実行する前に取る。持っている間は実行ファイルが入れ替わらない
+    ///
+    /// 待つのは入れ替えている間 (renameの間) だけ。
+    pub async fn acquire_executable(&self) -> OwnedRwLockReadGuard<()> {
+        self.executable_lock.clone().read_owned().await
+    }
+
+    /// 長く動き続ける処理 (ダウンロード) 用。入れ替え中・入れ替え待ちなら None
+    pub fn try_acquire_executable(&self) -> Option<OwnedRwLockReadGuard<()>> {
+        if self.swap_pending.load(Ordering::SeqCst) {
+            return None;
+        }
+        self.executable_lock.clone().try_read_owned().ok()
+    }
+
+    /// 実行中のyt-dlpが終わるのを待ってから入れ替え用のロックを取る
+    ///
+    /// 待っている間も短い処理 (再生・チャンネル情報) は実行でき、新しいダウンロードだけ止める。
+    async fn lock_for_swap(&self, app_handle: Option<&AppHandle>) -> OwnedRwLockWriteGuard<()> {
+        let mut notified = false;
+        loop {
+            if let Ok(guard) = self.executable_lock.clone().try_write_owned() {
+                self.swap_pending.store(false, Ordering::SeqCst);
+                return guard;
+            }
+            self.swap_pending.store(true, Ordering::SeqCst);
+            if let (Some(app_handle), false) = (app_handle, notified) {
+                let _ = emit_status(app_handle, "実行中の処理の終了を待っています...".to_string(), DOWNLOAD_PROGRESS_END);
+                notified = true;
+            }
+            tokio::time::sleep(SWAP_RETRY_INTERVAL).await;
+        }
+    }
+
+    /// 署名の検証に使う鍵 (組み込みのyt-dlpの鍵と設定の鍵)
+    fn trusted_keys(&self) -> Result<TrustedKeys> {
+        let mut keys = TrustedKeys::ytdlp().context("組み込みの公開鍵の読み込みに失敗")?;
+        if let Some(path) = self.settings().signing_key_path.filter(|p| !p.trim().is_empty()) {
+            let data = std::fs::read(&path).with_context(|| format!("公開鍵のファイルを読めません: {}", path))?;
+            keys.extend(TrustedKeys::parse(&data).with_context(|| format!("公開鍵の読み込みに失敗: {}", path))?);
+        }
+        if keys.is_empty() {
+            return Err(anyhow!("署名を検証する公開鍵がありません (keys/yt-dlp-public.asc が空で、設定にも鍵がありません)"));
+        }
+        Ok(keys)
+    }
+
+    /// リリースごとの作業ディレクトリ
+    ///
+    /// 一時ディレクトリは別のファイルシステムのことがありrenameできないため、実行ファイルの横に作る。
+    /// バージョンごとに分けて、途中まで取ったファイルを別のバージョンで続けないようにする。
+    fn staging_dir(&self, tag: &str) -> PathBuf {
+        self.app_data_dir.join(STAGING_DIR_NAME).join(tag)
+    }
+
+    /// 別のバージョン用に途中まで取ったファイルを消す
+    fn clear_stale_staging(&self, tag: &str) {
+        let Ok(entries) = std::fs::read_dir(self.app_data_dir.join(STAGING_DIR_NAME)) else { return };
+        for entry in entries.flatten().filter(|e| e.file_name() != tag) {
+            let _ = std::fs::remove_dir_all(entry.path());
+        }
+    }
+
+    fn executable_path(&self) -> PathBuf {
+        self.app_data_dir.join(YTDLP_EXECUTABLE_NAME)
+    }
+
+    fn version_file_path(&self) -> PathBuf {
+        self.app_data_dir.join(VERSION_FILE_NAME)
+    }
+
+    pub fn settings(&self) -> UpdaterSettings {
+        self.settings.lock().unwrap().clone()
+    }
+
+    pub fn set_settings(&self, settings: UpdaterSettings) -> Result<()> {
+        let path = self.app_data_dir.join(SETTINGS_FILE_NAME);
+        let temp_path = path.with_extension("json.tmp");
+        std::fs::write(&temp_path, serde_json::to_string_pretty(&settings)?)?;
+        std::fs::rename(&temp_path, &path)?;
+        *self.settings.lock().unwrap() = settings;
+        self.wake.notify_one();
+        Ok(())
+    }
+
+    /// 実行ファイルがあるときだけバージョンを返す
+    pub fn installed_versions(&self) -> InstalledVersions {
+        let current = self.executable_path().exists()
+            .then(|| read_version_file(&self.version_file_path()))
+            .flatten();
+        let previous = with_previous_suffix(&self.executable_path()).exists()
+            .then(|| read_version_file(&with_previous_suffix(&self.version_file_path())))
+            .flatten();
+        InstalledVersions { current, previous }
+    }
+
+    /// 検証済みのファイルをインストールする。今のバージョンはロールバック用に残す
+    ///
+    /// `verified_path` は実行ファイルと同じファイルシステムにあること。
+    /// 実行中のyt-dlpが終わるのを待ち、入れ替えはrenameだけで行う。
+    async fn install(&self, app_handle: &AppHandle, verified_path: &Path, tag: &str) -> Result<()> {
+        // 入れ替えた瞬間に実行できる状態にしておく
+        #[cfg(not(target_os = "windows"))]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(verified_path, std::fs::Permissions::from_mode(0o755))?;
+        }
+        std::fs::File::open(verified_path)?.sync_all()?;
+        let staged_version = verified_path.with_file_name(VERSION_FILE_NAME);
+        std::fs::write(&staged_version, tag)?;
+
+        let _guard = self.lock_for_swap(Some(app_handle)).await;
+
+        let exe_path = self.executable_path();
+        let version_path = self.version_file_path();
+        let previous_exe = with_previous_suffix(&exe_path);
+        let previous_version_path = with_previous_suffix(&version_path);
+
+        let had_current = exe_path.exists();
+        if had_current {
+            keep_as_previous(&exe_path, &previous_exe)?;
+            if version_path.exists() {
+                keep_as_previous(&version_path, &previous_version_path)?;
+            } else {
+                let _ = std::fs::remove_file(&previous_version_path);
+            }
+        }
+
+        // 同じファイルシステム内のrenameなので、元のパスは古いか新しいかのどちらかになる
+        if let Err(e) = std::fs::rename(verified_path, &exe_path) {
+            // 今のバージョンを使えるままにする
+            if had_current {
+                let _ = std::fs::rename(&previous_exe, &exe_path);
+                let _ = std::fs::rename(&previous_version_path, &version_path);
+            }
+            return Err(anyhow!("実行ファイルの入れ替えに失敗: {}", e));
+        }
+        std::fs::rename(&staged_version, &version_path)?;
+        Ok(())
+    }
+
+    /// ひとつ前のバージョンと入れ替え、戻したバージョンに固定する
+    ///
+    /// 固定しないと次の更新確認で最新に戻ってしまうため。解除は設定から行う。
+    pub async fn rollback(&self) -> Result<String> {
+        // 更新・手元のファイルからのインストールの途中で入れ替えないようにする
+        let _updating = self.update_lock.lock().await;
+        let _guard = self.lock_for_swap(None).await;
+        let exe_path = self.executable_path();
+        let version_path = self.version_file_path();
+        let previous_exe = with_previous_suffix(&exe_path);
+        let previous_version_path = with_previous_suffix(&version_path);
+        let previous_version = read_version_file(&previous_version_path)
+            .filter(|_| previous_exe.exists())
+            .ok_or_else(|| anyhow!("戻せる以前のバージョンがありません"))?;
+
+        let swap = |a: &Path, b: &Path| -> Result<()> {
+            let temp = a.with_extension("swap");
+            std::fs::rename(a, &temp)?;
+            std::fs::rename(b, a)?;
+            std::fs::rename(&temp, b)?;
+            Ok(())
+        };
+        swap(&exe_path, &previous_exe)?;
+        if version_path.exists() {
+            swap(&version_path, &previous_version_path)?;
+        } else {
+            std::fs::rename(&previous_version_path, &version_path)?;
+        }
+
+        let mut settings = self.settings();
+        settings.pinned_version = Some(previous_version.clone());
+        self.set_settings(settings)?;
+        Ok(previous_version)
+    }
 }
 
 
 // --- 公開関数 ---
 
 /// yt-dlpの更新を確認し、必要であればダウンロードと検証を行う
+///
+/// 設定の系統 (stable・nightly・master) の最新、または固定したバージョンにする。
 #[tauri::command]
 pub async fn check_and_update(app_handle: tauri::AppHandle) -> Result<String, String> {
-    let emit_data = EmitterData {
-        status: "更新を確認中...".to_string(),
-        progress: 0
+    let manager = app_handle.state::<YtDlpManager>();
+    run_update(&app_handle, &manager).await.map_err(|e| e.to_string())
+}
+
+/// 更新を確認するだけでインストールはしない
+///
+/// 新しいバージョンとリリースノートを返し、結果は定期確認の結果としても残す。
+#[tauri::command]
+pub async fn check_for_update(manager: State<'_, YtDlpManager>) -> Result<UpdateInfo, String> {
+    check_release(&manager).await.map_err(|e| e.to_string())
+}
+
+async fn check_release(manager: &YtDlpManager) -> Result<UpdateInfo> {
+    let settings = manager.settings();
+    let installed = manager.installed_versions().current;
+    let http_client = reqwest::Client::builder().connect_timeout(CONNECT_TIMEOUT).build()?;
+    let release = match get_release(&http_client, &settings, settings.pinned_version.as_deref()).await {
+        Ok(release) => release,
+        Err(e) => {
+            manager.record_check(None)?;
+            return Err(e);
+        }
     };
-    app_handle.emit("ytdlp_status", emit_data).map_err(|e| e.to_string())?;
-    
-    let local_version = get_local_version(&app_handle).map_err(|e| e.to_string())?;
-    let http_client = reqwest::Client::new();
-    let latest_release = get_latest_release(&http_client).await.map_err(|e| e.to_string())?;
-    
-    if local_version >= latest_release.tag_name {
-        println!("yt-dlpは最新です: {}", local_version);
-        let emit_data = EmitterData {
-            status: format!("準備完了 (バージョン: {})", local_version),
-            progress: 100
-        };
-        
-        app_handle.emit("ytdlp_status", emit_data).map_err(|e| e.to_string())?;
+
+    let pinned = settings.pinned_version.is_some();
+    let info = UpdateInfo {
+        update_available: !is_up_to_date(installed.as_deref(), &release.tag_name, pinned)?,
+        current: installed,
+        latest: release.tag_name,
+        pinned,
+        release_notes: release.body.filter(|body| !body.trim().is_empty()),
+        release_url: release.html_url,
+        published_at: release.published_at,
+        checked_at: now_secs(),
+    };
+    manager.record_check(Some(&info))?;
+    Ok(info)
+}
+
+/// 定期確認。更新があれば設定に従ってインストールするか、`ytdlp_update_available` で知らせる
+async fn run_background_check(app_handle: &AppHandle, manager: &YtDlpManager) -> Result<()> {
+    let info = check_release(manager).await?;
+    if !info.update_available {
+        return Ok(());
+    }
+    println!("yt-dlpの更新があります: {:?} -> {}", info.current, info.latest);
+    if manager.settings().auto_install {
+        run_update(app_handle, manager).await?;
+    } else {
+        app_handle.emit("ytdlp_update_available", &info)?;
+    }
+    Ok(())
+}
+
+/// 抽出エラーのあとに更新を試し、新しいバージョンが入ったら true
+///
+/// 固定しているとき、設定で無効のとき、前回試してから `EXTRACTOR_UPDATE_COOLDOWN` 経っていないときは何もしない。
+pub async fn update_after_extractor_error(app_handle: &AppHandle) -> bool {
+    let manager = app_handle.state::<YtDlpManager>();
+    let settings = manager.settings();
+    if !settings.update_on_extractor_error || settings.pinned_version.is_some() {
+        return false;
+    }
+    {
+        let mut last = manager.last_extractor_update.lock().unwrap();
+        if last.is_some_and(|t| t.elapsed() < EXTRACTOR_UPDATE_COOLDOWN) {
+            return false;
+        }
+        *last = Some(Instant::now());
+    }
+
+    println!("抽出に失敗したため、yt-dlpの更新を確認します");
+    match run_update(app_handle, &manager).await {
+        Ok(result) => result == "updated",
+        Err(e) => {
+            println!("Failed to update yt-dlp after extractor error: {}", e);
+            false
+        }
+    }
+}
+
+async fn run_update(app_handle: &AppHandle, manager: &YtDlpManager) -> Result<String> {
+    // 画面からの更新・定期確認・抽出エラーからの更新が重なったら、後のものは前の結果を見て判断する
+    let _updating = manager.update_lock.lock().await;
+    emit_status(app_handle, "更新を確認中...".to_string(), 0)?;
+
+    let settings = manager.settings();
+    let installed = manager.installed_versions().current;
+    let http_client = reqwest::Client::builder().connect_timeout(CONNECT_TIMEOUT).build()?;
+    let release = get_release(&http_client, &settings, settings.pinned_version.as_deref()).await?;
+
+    if is_up_to_date(installed.as_deref(), &release.tag_name, settings.pinned_version.is_some())? {
+        let version = installed.unwrap_or_default();
+        println!("yt-dlpは最新です: {}", version);
+        emit_status(app_handle, format!("準備完了 (バージョン: {})", version), 100)?;
         return Ok("already_latest".to_string());
     }
 
-    println!("新しいバージョンが見つかりました: {} -> {}", local_version, latest_release.tag_name);
-    let emit_data = EmitterData {
-        status: format!("新バージョン {} をダウンロード中...", latest_release.tag_name),
-        progress: 33
-    };
-    app_handle.emit("ytdlp_status", emit_data).map_err(|e| e.to_string())?;
+    println!("新しいバージョンが見つかりました: {:?} -> {}", installed, release.tag_name);
+    emit_status(app_handle, format!("新バージョン {} をダウンロード中...", release.tag_name), DOWNLOAD_PROGRESS_START)?;
 
-    let exe_asset = latest_release.assets.iter().find(|a| a.name == YTDLP_EXECUTABLE_NAME).ok_or("実行ファイルが見つかりません".to_string())?;
-    let hash_asset = latest_release.assets.iter().find(|a| a.name == "SHA2-256SUMS").ok_or("ハッシュファイルが見つかりません".to_string())?;
+    let exe_asset = select_asset(&release.assets)
+        .ok_or_else(|| anyhow!("この環境用の実行ファイルが見つかりません (候補: {})", YTDLP_ASSET_CANDIDATES.join(", ")))?;
+    println!("使用するアセット: {}", exe_asset.name);
+    let hash_asset = release.assets.iter().find(|a| a.name == HASHES_FILE_NAME).ok_or_else(|| anyhow!("ハッシュファイルが見つかりません"))?;
+    // 署名の無いリリースは入れない
+    let signature_asset = release.assets.iter().find(|a| a.name == SIGNATURE_FILE_NAME).ok_or_else(|| anyhow!("ハッシュファイルの署名が見つかりません"))?;
+    // 鍵が読めないならダウンロードする前に止める
+    manager.trusted_keys()?;
 
-    let temp_dir = app_handle.path().temp_dir().map_err(|e| e.to_string())?;
-    let temp_exe_path = temp_dir.join(YTDLP_EXECUTABLE_NAME);
-    let temp_hash_path = temp_dir.join("SHA2-256SUMS");
+    manager.clear_stale_staging(&release.tag_name);
+    let staging_dir = manager.staging_dir(&release.tag_name);
+    std::fs::create_dir_all(&staging_dir)?;
 
-    download_file(&http_client, &hash_asset.browser_download_url, &temp_hash_path).await.map_err(|e| e.to_string())?;
-    download_file(&http_client, &exe_asset.browser_download_url, &temp_exe_path).await.map_err(|e| e.to_string())?;
-    
-    let emit_data = EmitterData {
-        status: "ハッシュを検証中...".to_string(),
-        progress: 66
-    };
-    app_handle.emit("ytdlp_status", emit_data).map_err(|e| e.to_string())?;
+    download_file(app_handle, &http_client, &hash_asset.browser_download_url, &staging_dir.join(HASHES_FILE_NAME)).await?;
+    download_file(app_handle, &http_client, &signature_asset.browser_download_url, &staging_dir.join(SIGNATURE_FILE_NAME)).await?;
+    download_file(app_handle, &http_client, &exe_asset.browser_download_url, &staging_dir.join(&exe_asset.name)).await?;
+
+    verify_and_install(app_handle, manager, &staging_dir, &exe_asset.name, &release.tag_name).await?;
+    Ok("updated".to_string())
+}
 
-    let hashes_content = std::fs::read_to_string(&temp_hash_path).map_err(|e| e.to_string())?;
-    let expected_hash = find_hash_for_file(&hashes_content, YTDLP_EXECUTABLE_NAME).ok_or("ハッシュが見つかりません".to_string())?;
-    let actual_hash = calculate_sha256(&temp_exe_path).map_err(|e| e.to_string())?;
+/// 作業ディレクトリのハッシュファイルの署名を確かめてから、実行ファイルをハッシュで検証する
+///
+/// 失敗したときは作業ディレクトリを消し、次回は最初から取り直す。
+/// 実行ファイルは作業ディレクトリにアセット名のまま置き、その名前でハッシュを引く。
+fn verify_staged(staging_dir: &Path, asset_name: &str, keys: &TrustedKeys) -> Result<()> {
+    let verified = verify_hashes_signature(staging_dir, keys);
+    if verified.is_err() {
+        let _ = std::fs::remove_dir_all(staging_dir);
+    }
+    let hashes_content = verified?;
+    let expected_hash = find_hash_for_file(&hashes_content, asset_name).ok_or_else(|| anyhow!("{} のハッシュが見つかりません", asset_name))?;
+    let actual_hash = calculate_sha256(&staging_dir.join(asset_name))?;
 
     if expected_hash != actual_hash {
-        return Err("ハッシュの検証に失敗しました！".to_string());
+        let _ = std::fs::remove_dir_all(staging_dir);
+        return Err(anyhow!("ハッシュの検証に失敗しました！"));
     }
-    
     println!("ハッシュの検証に成功しました。");
+    Ok(())
+}
+
+/// 署名が正しいときだけハッシュファイルの中身を返す
+fn verify_hashes_signature(staging_dir: &Path, keys: &TrustedKeys) -> Result<String> {
+    let hashes = std::fs::read(staging_dir.join(HASHES_FILE_NAME))?;
+    let signature = std::fs::read(staging_dir.join(SIGNATURE_FILE_NAME))
+        .map_err(|_| anyhow!("{} がありません", SIGNATURE_FILE_NAME))?;
+    keys.verify_detached(&hashes, &signature)
+        .map_err(|e| anyhow!("{} の署名の検証に失敗しました: {}", HASHES_FILE_NAME, e))?;
+    println!("{} の署名の検証に成功しました。", HASHES_FILE_NAME);
+    Ok(String::from_utf8(hashes)?)
+}
 
-    let final_exe_path = get_executable_path(&app_handle).map_err(|e| e.to_string())?;
-    if let Some(parent) = final_exe_path.parent() {
-        if !parent.exists() { std::fs::create_dir_all(parent).map_err(|e| e.to_string())?; }
+/// 作業ディレクトリの実行ファイルを検証してインストールする
+async fn verify_and_install(app_handle: &AppHandle, manager: &YtDlpManager, staging_dir: &Path, asset_name: &str, tag: &str) -> Result<()> {
+    emit_status(app_handle, "署名とハッシュを検証中...".to_string(), DOWNLOAD_PROGRESS_END)?;
+    verify_staged(staging_dir, asset_name, &manager.trusted_keys()?)?;
+
+    let staged_exe_path = staging_dir.join(asset_name);
+    let installed = manager.install(app_handle, &staged_exe_path, tag).await;
+    // 入れ替えの間に待たせていたダウンロードを始める
+    app_handle.state::<DownloadManager>().pump();
+    installed?;
+    let _ = std::fs::remove_dir_all(staging_dir);
+
+    emit_status(app_handle, format!("更新完了 (バージョン: {})", tag), 100)?;
+    println!("yt-dlpの更新が完了しました。");
+    Ok(())
+}
+
+/// 実行ファイル自身に `--version` を聞く (検証後のファイルにだけ使う)
+async fn query_version(executable: &Path) -> Result<String> {
+    let output = tokio::process::Command::new(executable)
+        .arg("--version")
+        .output()
+        .await
+        .context("yt-dlpを実行できません")?;
+    let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() || YtDlpVersion::parse(&version).is_none() {
+        return Err(anyhow!("yt-dlpのバージョンを取得できません"));
+    }
+    Ok(version)
+}
+
+/// 手元のファイルをどのアセットとして検証するか
+///
+/// ファイル名がこの環境の候補ならその名前、そうでなければ (名前を変えて保存した場合など) 候補のうちハッシュファイルに載っているもの。
+/// 別の環境向けのファイルはハッシュが合わずに失敗する。
+fn local_asset_name(executable_path: &Path, hashes_content: &str) -> Result<String> {
+    let file_name = executable_path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    YTDLP_ASSET_CANDIDATES.iter()
+        .filter(|name| find_hash_for_file(hashes_content, name).is_some())
+        .find(|name| **name == file_name)
+        .or_else(|| YTDLP_ASSET_CANDIDATES.iter().find(|name| find_hash_for_file(hashes_content, name).is_some()))
+        .map(|name| name.to_string())
+        .ok_or_else(|| anyhow!("{} にこの環境用の実行ファイルが載っていません", HASHES_FILE_NAME))
+}
+
+/// ネットワークを使わず、手元の実行ファイルとハッシュファイルからインストールする
+///
+/// バージョンは検証後に実行ファイルから読み取る。`version` を指定したときは読み取ったものと一致しなければ入れない。
+async fn install_from_file(
+    app_handle: &AppHandle,
+    manager: &YtDlpManager,
+    executable_path: &Path,
+    hashes_path: &Path,
+    signature_path: &Path,
+    version: Option<String>,
+) -> Result<String> {
+    if let Some(version) = &version {
+        YtDlpVersion::parse(version).ok_or_else(|| anyhow!("バージョンの形式が不明です: {}", version))?;
     }
-    std::fs::rename(&temp_exe_path, &final_exe_path).map_err(|e| e.to_string())?;
 
+    let _updating = manager.update_lock.lock().await;
+    emit_status(app_handle, "ファイルをコピー中...".to_string(), DOWNLOAD_PROGRESS_START)?;
+    // 選んだファイルは別のドライブにあることが多いので、コピーしてからrenameで入れ替える
+    let staging_dir = manager.staging_dir(LOCAL_STAGING_NAME);
+    let _ = std::fs::remove_dir_all(&staging_dir);
+    std::fs::create_dir_all(&staging_dir)?;
+    std::fs::copy(hashes_path, staging_dir.join(HASHES_FILE_NAME)).context("ハッシュファイルのコピーに失敗")?;
+    std::fs::copy(signature_path, staging_dir.join(SIGNATURE_FILE_NAME)).context("署名ファイルのコピーに失敗")?;
+
+    let asset_name = local_asset_name(executable_path, &std::fs::read_to_string(staging_dir.join(HASHES_FILE_NAME))?)?;
+    let staged_exe_path = staging_dir.join(&asset_name);
+    std::fs::copy(executable_path, &staged_exe_path).context("実行ファイルのコピーに失敗")?;
+
+    // 実行する前に検証する
+    verify_staged(&staging_dir, &asset_name, &manager.trusted_keys()?)?;
     #[cfg(not(target_os = "windows"))]
     {
         use std::os::unix::fs::PermissionsExt;
-        std::fs::set_permissions(&final_exe_path, std::fs::Permissions::from_mode(0o755)).map_err(|e| e.to_string())?;
+        std::fs::set_permissions(&staged_exe_path, std::fs::Permissions::from_mode(0o755))?;
     }
+    let actual_version = query_version(&staged_exe_path).await.and_then(|actual| match &version {
+        // 履歴や更新の確認が間違ったバージョンで動かないようにする
+        Some(claimed) if YtDlpVersion::parse(claimed) != YtDlpVersion::parse(&actual) => {
+            Err(anyhow!("指定したバージョン {} と実行ファイルのバージョン {} が一致しません", claimed, actual))
+        }
+        _ => Ok(actual),
+    });
+    let actual_version = match actual_version {
+        Ok(version) => version,
+        Err(e) => {
+            let _ = std::fs::remove_dir_all(&staging_dir);
+            return Err(e);
+        }
+    };
 
-    let version_file_path = app_handle.path().app_data_dir().map_err(|e| e.to_string())?.join(VERSION_FILE_NAME);
-    std::fs::write(version_file_path, latest_release.tag_name.clone()).map_err(|e| e.to_string())?;
+    verify_and_install(app_handle, manager, &staging_dir, &asset_name, &actual_version).await?;
+    Ok(actual_version)
+}
 
-    let emit_data = EmitterData {
-        status: format!("更新完了 (バージョン: {})", latest_release.tag_name),
-        progress: 100
-    };
-    app_handle.emit("ytdlp_status", emit_data).map_err(|e| e.to_string())?;
-    println!("yt-dlpの更新が完了しました。");
-    
-    Ok("updated".to_string())
+
+// --- Tauri Commands ---
+
+#[tauri::command]
+pub fn get_ytdlp_updater_settings(manager: State<'_, YtDlpManager>) -> Result<UpdaterSettings, String> {
+    Ok(manager.settings())
+}
+
+#[tauri::command]
+pub fn set_ytdlp_updater_settings(manager: State<'_, YtDlpManager>, settings: UpdaterSettings) -> Result<(), String> {
+    manager.set_settings(settings).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_ytdlp_versions(manager: State<'_, YtDlpManager>) -> Result<InstalledVersions, String> {
+    Ok(manager.installed_versions())
+}
+
+/// ひとつ前のバージョンに戻し、そのバージョンを返す
+#[tauri::command]
+pub async fn rollback_ytdlp(app_handle: AppHandle, manager: State<'_, YtDlpManager>) -> Result<String, String> {
+    let result = manager.rollback().await;
+    app_handle.state::<DownloadManager>().pump();
+    result.map_err(|e| e.to_string())
 }
 
+/// ダウンロード済みの実行ファイルを署名付きの `SHA2-256SUMS` で検証してインストールし、バージョンを返す
+#[tauri::command]
+pub async fn install_ytdlp_from_file(
+    app_handle: AppHandle,
+    manager: State<'_, YtDlpManager>,
+    executable_path: String,
+    hashes_path: String,
+    signature_path: String,
+    version: Option<String>,
+) -> Result<String, String> {
+    install_from_file(&app_handle, &manager, Path::new(&executable_path), Path::new(&hashes_path), Path::new(&signature_path), version)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 最後に確認した結果 (まだ確認していなければ null)
+#[tauri::command]
+pub fn get_last_ytdlp_update_check(manager: State<'_, YtDlpManager>) -> Result<Option<UpdateInfo>, String> {
+    Ok(manager.last_check())
+}
@@ -1,6 +1,5 @@
 
 
-use tauri_plugin_shell::ShellExt;
 use tauri::Manager;
 use serde_json::{Value, json};
 use serde::{Deserialize, Serialize};
@@ -8,6 +7,9 @@ use encoding_rs::SHIFT_JIS;
 use std::{str};
 use std::path::PathBuf;
 
+use crate::dlp::runner::run_ytdlp;
+use crate::thumbnail::cache::{spawn_prefetch, ThumbnailVariant};
+
 
 
 /// yt-dlpの実行ファイルパスを取得
@@ -23,26 +25,20 @@ pub fn get_executable_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, Str
 
 #[tauri::command]
 pub async fn dlp_get_channel_info(app_handle: tauri::AppHandle, channel_url: String) -> Result<String, String> {
-    let shell = app_handle.shell();
-
     println!("Fetching channel info for URL: {}", channel_url);
 
     println!("Using yt-dlp executable at: {:?}", get_executable_path(&app_handle));
 
     // yt-dlpでチャンネル情報をJSON形式で取得
-    let output = shell
-        .sidecar(get_executable_path(&app_handle).map_err(|e| format!("Failed to get executable path: {}", e))?)
-        .unwrap()
-        .arg("--no-warnings")
-        .arg("--cookies-from-browser")
-        .arg("firefox")
-        .arg("-J")
-        .arg("--playlist-items")
-        .arg("1")
-        .arg(&channel_url)
-        .output()
-        .await
-        .map_err(|e| format!("Failed to execute yt-dlp: {}", e))?;
+    let output = run_ytdlp(&app_handle, &[
+        "--no-warnings",
+        "--cookies-from-browser",
+        "firefox",
+        "-J",
+        "--playlist-items",
+        "1",
+        &channel_url,
+    ]).await?;
 
 
     if !output.status.success() {
@@ -87,6 +83,8 @@ pub async fn dlp_get_channel_info(app_handle: tauri::AppHandle, channel_url: Str
         .unwrap_or("No thumbnail available");
     println!("Channel Thumbnail (Last): {}", thumbnail_last);
 
+    spawn_prefetch(&app_handle, vec![thumbnail_last.to_string()], ThumbnailVariant::Medium);
+
     // チャンネル情報をJSON形式で返す
     let channel_info = json!({
         "channel_id": channel_id,
@@ -115,40 +113,34 @@ struct Record {
 
 #[tauri::command]
 pub async fn dlp_get_channel_newvideo(app_handle: tauri::AppHandle, channel_url: String) -> Result<String, String> {
-        let shell = app_handle.shell();
-
     println!("Fetching channel info for URL: {}", channel_url);
 
     println!("Using yt-dlp executable at: {:?}", get_executable_path(&app_handle));
 
 
     // yt-dlpでチャンネル情報をJSON形式で取得
-    let output = shell
-        .sidecar(get_executable_path(&app_handle).map_err(|e| format!("Failed to get executable path: {}", e))?)
-        .unwrap()
-        .arg("--no-warnings")
-        .arg("--cookies-from-browser")
-        .arg("firefox")
-        .arg("--playlist-items")
-        .arg("1-8")
-        .arg("--match-filter")
-        .arg("!is_live & !was_live & availability = 'public'")
-        .arg("--print")
-        .arg("%(webpage_url)s")
-        .arg("--print")
-        .arg("%(title)s")
-        .arg("--print")
-        .arg("%(id)s")
-        .arg("--print")
-        .arg("%(upload_date)s")
-        .arg("--print")
-        .arg("%(view_count)s")
-        .arg("--print")
-        .arg("%(thumbnail)s")
-        .arg(&channel_url)
-        .output()
-        .await
-        .map_err(|e| format!("Failed to execute yt-dlp: {}", e))?;
+    let output = run_ytdlp(&app_handle, &[
+        "--no-warnings",
+        "--cookies-from-browser",
+        "firefox",
+        "--playlist-items",
+        "1-8",
+        "--match-filter",
+        "!is_live & !was_live & availability = 'public'",
+        "--print",
+        "%(webpage_url)s",
+        "--print",
+        "%(title)s",
+        "--print",
+        "%(id)s",
+        "--print",
+        "%(upload_date)s",
+        "--print",
+        "%(view_count)s",
+        "--print",
+        "%(thumbnail)s",
+        &channel_url,
+    ]).await?;
 
      // レコードは2つの改行コード `[10, 10]` で区切られているため、それで分割する
     let records_bytes = output.stdout.split(|w| *w == 10).filter(|s| !s.is_empty());
@@ -181,6 +173,8 @@ pub async fn dlp_get_channel_newvideo(app_handle: tauri::AppHandle, channel_url:
         video_infos.push(info);
     }
     
+    spawn_prefetch(&app_handle, video_infos.iter().map(|v| v.thumbnail_url.clone()).collect(), ThumbnailVariant::Medium);
+
     // 結果をきれいに表示
     for (i, info) in video_infos.iter().enumerate() {
         println!("--- 動画 {} ---", i + 1);
@@ -196,39 +190,33 @@ pub async fn dlp_get_channel_newvideo(app_handle: tauri::AppHandle, channel_url:
 
 #[tauri::command]
 pub async fn dlp_get_channel_morevideo(app_handle: tauri::AppHandle, channel_url: String, offset: u32) -> Result<String, String> {
-        let shell = app_handle.shell();
-
     println!("Fetching channel info for URL: {}", channel_url);
     println!("Offset: {}", offset);
     println!("Using yt-dlp executable at: {:?}", get_executable_path(&app_handle));
 
 
     // yt-dlpでチャンネル情報をJSON形式で取得
-    let output = shell
-        .sidecar(get_executable_path(&app_handle).map_err(|e| format!("Failed to get executable path: {}", e))?)
-        .unwrap()
-        .arg("--no-warnings")
-        .arg("--cookies-from-browser")
-        .arg("firefox")
-        .arg("--playlist-items")
-        .arg(format!("{}-{}", offset + 1, offset + 6)) // オフセットを考慮して取得
-        .arg("!is_live & !was_live & availability = 'public'")
-        .arg("--print")
-        .arg("%(webpage_url)s")
-        .arg("--print")
-        .arg("%(title)s")
-        .arg("--print")
-        .arg("%(id)s")
-        .arg("--print")
-        .arg("%(upload_date)s")
-        .arg("--print")
-        .arg("%(view_count)s")
-        .arg("--print")
-        .arg("%(thumbnail)s")
-        .arg(&channel_url)
-        .output()
-        .await
-        .map_err(|e| format!("Failed to execute yt-dlp: {}", e))?;
+    let output = run_ytdlp(&app_handle, &[
+        "--no-warnings",
+        "--cookies-from-browser",
+        "firefox",
+        "--playlist-items",
+        &format!("{}-{}", offset + 1, offset + 6), // オフセットを考慮して取得
+        "!is_live & !was_live & availability = 'public'",
+        "--print",
+        "%(webpage_url)s",
+        "--print",
+        "%(title)s",
+        "--print",
+        "%(id)s",
+        "--print",
+        "%(upload_date)s",
+        "--print",
+        "%(view_count)s",
+        "--print",
+        "%(thumbnail)s",
+        &channel_url,
+    ]).await?;
 
      // レコードは2つの改行コード `[10, 10]` で区切られているため、それで分割する
     let records_bytes = output.stdout.split(|w| *w == 10).filter(|s| !s.is_empty());
@@ -261,6 +249,8 @@ pub async fn dlp_get_channel_morevideo(app_handle: tauri::AppHandle, channel_url
         video_infos.push(info);
     }
     
+    spawn_prefetch(&app_handle, video_infos.iter().map(|v| v.thumbnail_url.clone()).collect(), ThumbnailVariant::Medium);
+
     // 結果をきれいに表示
     for (i, info) in video_infos.iter().enumerate() {
         println!("--- 動画 {} ---", i + 1);
@@ -287,35 +277,29 @@ struct UrlVideoInfo {
 
 #[tauri::command]
 pub async fn dlp_get_video_info(app_handle: tauri::AppHandle, video_url: String) -> Result<String, String> {
-    let shell = app_handle.shell();
-
     println!("Fetching video info for URL: {}", video_url);
     println!("Using yt-dlp executable at: {:?}", get_executable_path(&app_handle));
 
 
     // yt-dlpで動画情報をJSON形式で取得
-    let output = shell
-        .sidecar(get_executable_path(&app_handle).map_err(|e| format!("Failed to get executable path: {}", e))?)
-        .unwrap()
-        .arg("--no-warnings")
-        .arg("--cookies-from-browser")
-        .arg("firefox")
-        .arg("--print")
-        .arg("%(title)s")
-        .arg("--print")
-        .arg("%(view_count)s")
-        .arg("--print")
-        .arg("%(like_count)s")
-        .arg("--print")
-        .arg("%(channel_url)s")
-        .arg("--print")
-        .arg("%(upload_date)s")
-        .arg("--print")
-        .arg("%(channel_follower_count)s")
-        .arg(&video_url)
-        .output()
-        .await
-        .map_err(|e| format!("Failed to execute yt-dlp: {}", e))?;
+    let output = run_ytdlp(&app_handle, &[
+        "--no-warnings",
+        "--cookies-from-browser",
+        "firefox",
+        "--print",
+        "%(title)s",
+        "--print",
+        "%(view_count)s",
+        "--print",
+        "%(like_count)s",
+        "--print",
+        "%(channel_url)s",
+        "--print",
+        "%(upload_date)s",
+        "--print",
+        "%(channel_follower_count)s",
+        &video_url,
+    ]).await?;
 
      // レコードは2つの改行コード `[10, 10]` で区切られているため、それで分割する
     let records_bytes = output.stdout.split(|w| *w == 10).filter(|s| !s.is_empty());
@@ -360,25 +344,19 @@ pub async fn dlp_get_video_info(app_handle: tauri::AppHandle, video_url: String)
 
 #[tauri::command]
 pub async fn dlp_get_stream_url(app_handle: tauri::AppHandle, video_url: String, format_id: String) -> Result<String, String> {
-    let shell = app_handle.shell();
-
     println!("Fetching stream URL for video: {}", video_url);
     println!("Using yt-dlp executable at: {:?}", get_executable_path(&app_handle));
 
 
     // yt-dlpで動画のストリームURLを取得
-    let output = shell
-        .sidecar(get_executable_path(&app_handle).map_err(|e| format!("Failed to get executable path: {}", e))?)
-        .unwrap()
-        .arg("--cookies-from-browser")
-        .arg("firefox")
-        .arg("-f")
-        .arg(&format_id)
-        .arg("-g")
-        .arg(&video_url)
-        .output()
-        .await
-        .map_err(|e| format!("Failed to execute yt-dlp: {}", e))?;
+    let output = run_ytdlp(&app_handle, &[
+        "--cookies-from-browser",
+        "firefox",
+        "-f",
+        &format_id,
+        "-g",
+        &video_url,
+    ]).await?;
 
     if !output.status.success() {
         return Err(format!(
@@ -1,2 +1,7 @@
 pub mod get_channel;
-pub mod dlp_manager;
\ No newline at end of file
+pub mod dlp_manager;
+pub mod resolve_stream;
+pub mod runner;
+pub mod signature;
+pub mod transfer;
+pub mod version;
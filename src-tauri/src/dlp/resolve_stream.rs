@@ -0,0 +1,187 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::dlp::runner::run_ytdlp;
+
+
+// --- 型定義 ---
+
+/// 設定画面の画質設定から組み立てるフォーマット選択条件
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamPreference {
+    /// "1080p" のような解像度
+    pub resolution: String,
+    /// "av1" | "h264" | "vp9"
+    pub codecs: String,
+    pub hfr: bool,
+    /// "high" | "medium" | "low"
+    pub audio_quality: String,
+}
+
+/// 解決済みストリーム1本分の情報
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolvedStream {
+    pub format_id: String,
+    pub url: String,
+    pub ext: String,
+    pub vcodec: Option<String>,
+    pub acodec: Option<String>,
+    pub width: Option<u64>,
+    pub height: Option<u64>,
+    pub fps: Option<f64>,
+    pub abr: Option<f64>,
+    pub tbr: Option<f64>,
+    pub filesize: Option<u64>,
+    /// URLの `expire` パラメータ (UNIX秒)
+    pub expires_at: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResolvedStreams {
+    /// 実際に選択されたフォーマット (例: "137+251")
+    pub format_id: String,
+    pub video: Option<ResolvedStream>,
+    pub audio: Option<ResolvedStream>,
+    /// 選択された全ストリーム (3本以上のマージにも対応)
+    pub streams: Vec<ResolvedStream>,
+    /// 全ストリームの中で最も早い有効期限
+    pub expires_at: Option<u64>,
+}
+
+
+// --- ヘルパー関数 ---
+
+/// 画質設定からyt-dlpのフォーマット指定文字列を作る
+pub fn build_format_selector(preference: &StreamPreference) -> String {
+    let height: String = preference.resolution.chars().take_while(|c| c.is_ascii_digit()).collect();
+    let height_filter = if height.is_empty() { String::new() } else { format!("[height<={}]", height) };
+
+    let codec_filter = match preference.codecs.as_str() {
+        "av1" => "[vcodec^=av01]",
+        "vp9" => "[vcodec^=vp9]",
+        _ => "[vcodec^=avc1]",
+    };
+    let fps_filter = if preference.hfr { "" } else { "[fps<=30]" };
+
+    let audio = match preference.audio_quality.as_str() {
+        "low" => "(wa[acodec=opus]/wa)",
+        "medium" => "(ba[abr<=96]/ba)",
+        _ => "ba",
+    };
+
+    // 完全一致 → コーデック無視 → 単一ファイルの順でフォールバックする
+    format!(
+        "bv*{h}{c}{f}+{a}/bv*{h}{f}+{a}/bv*{h}+{a}/b{h}/b",
+        h = height_filter,
+        c = codec_filter,
+        f = fps_filter,
+        a = audio,
+    )
+}
+
+/// ストリームURLのクエリから有効期限を取り出す
+fn parse_expiry(stream_url: &str) -> Option<u64> {
+    let parsed = url::Url::parse(stream_url).ok()?;
+    parsed.query_pairs()
+        .find(|(key, _)| key == "expire")
+        .and_then(|(_, value)| value.parse().ok())
+}
+
+fn non_empty_codec(value: &Value) -> Option<String> {
+    value.as_str()
+        .filter(|codec| !codec.is_empty() && *codec != "none")
+        .map(str::to_string)
+}
+
+fn to_resolved_stream(format: &Value) -> Option<ResolvedStream> {
+    let url = format["url"].as_str()?.to_string();
+    Some(ResolvedStream {
+        format_id: format["format_id"].as_str().unwrap_or_default().to_string(),
+        expires_at: parse_expiry(&url),
+        url,
+        ext: format["ext"].as_str().unwrap_or_default().to_string(),
+        vcodec: non_empty_codec(&format["vcodec"]),
+        acodec: non_empty_codec(&format["acodec"]),
+        width: format["width"].as_u64(),
+        height: format["height"].as_u64(),
+        fps: format["fps"].as_f64(),
+        abr: format["abr"].as_f64(),
+        tbr: format["tbr"].as_f64(),
+        filesize: format["filesize"].as_u64().or_else(|| format["filesize_approx"].as_u64()),
+    })
+}
+
+/// `-J` の出力から選択されたストリームを取り出す
+pub fn parse_resolved_streams(json_value: &Value) -> Result<ResolvedStreams, String> {
+    // マージ指定の場合は requested_formats に各ストリームが入り、
+    // 単一フォーマットの場合はトップレベルにURLが入る
+    let streams: Vec<ResolvedStream> = match json_value["requested_formats"].as_array() {
+        Some(formats) => formats.iter().filter_map(to_resolved_stream).collect(),
+        None => to_resolved_stream(json_value).into_iter().collect(),
+    };
+
+    if streams.is_empty() {
+        return Err("No stream URL found in yt-dlp output".to_string());
+    }
+
+    let format_id = json_value["format_id"].as_str()
+        .map(str::to_string)
+        .unwrap_or_else(|| streams.iter().map(|s| s.format_id.as_str()).collect::<Vec<_>>().join("+"));
+    let expires_at = streams.iter().filter_map(|s| s.expires_at).min();
+
+    let video = streams.iter().position(|s| s.vcodec.is_some());
+    let audio = streams.iter().position(|s| s.acodec.is_some() && s.vcodec.is_none())
+        .or_else(|| streams.iter().position(|s| s.acodec.is_some()));
+
+    Ok(ResolvedStreams {
+        format_id,
+        video: video.map(|i| streams[i].clone()),
+        audio: audio.map(|i| streams[i].clone()),
+        streams,
+        expires_at,
+    })
+}
+
+
+// --- Tauri Commands ---
+
+/// フォーマット指定 (例: "137+251") を1回の抽出で解決し、全ストリームのURLを返す
+///
+/// `format_selector` が無い場合は `preference` から選択条件を組み立てる。
+#[tauri::command]
+pub async fn dlp_resolve_streams(
+    app_handle: tauri::AppHandle,
+    video_url: String,
+    format_selector: Option<String>,
+    preference: Option<StreamPreference>,
+) -> Result<ResolvedStreams, String> {
+    let selector = match (format_selector, preference) {
+        (Some(selector), _) => selector,
+        (None, Some(preference)) => build_format_selector(&preference),
+        (None, None) => "bv*+ba/b".to_string(),
+    };
+
+    println!("Resolving streams for video: {} ({})", video_url, selector);
+    let output = run_ytdlp(&app_handle, &[
+        "--no-warnings",
+        "--cookies-from-browser",
+        "firefox",
+        "-f",
+        &selector,
+        "-J",
+        &video_url,
+    ]).await?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "yt-dlp error: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    let json_value: Value = serde_json::from_slice(&output.stdout)
+        .map_err(|e| format!("Failed to parse JSON: {}", e))?;
+
+    parse_resolved_streams(&json_value)
+}
@@ -0,0 +1,99 @@
+use tauri::{AppHandle, Manager};
+use tauri_plugin_shell::ShellExt;
+use tauri_plugin_shell::process::Output;
+
+use crate::dlp::dlp_manager::{update_after_extractor_error, YtDlpManager};
+use crate::dlp::get_channel::get_executable_path;
+use crate::download::manager::DownloadManager;
+
+// --- 定数定義 ---
+/// サイト側の変更に抽出処理が追いついていないときのエラー (小文字で比べる)
+///
+/// yt-dlpは想定外の抽出エラーにだけ「最新版か確認して」という案内を付ける。
+/// 通信エラー・地域制限・非公開動画などには付かないので、"unable to extract" などの一般的な文言では判断しない。
+const OUTDATED_EXTRACTOR_PATTERNS: &[&str] = &[
+    "confirm you are on the latest version",
+    "update to the latest version",
+    "nsig extraction failed",
+    "signature extraction failed",
+];
+
+
+// --- プライベートヘルパー関数 ---
+
+async fn run_once(app_handle: &AppHandle, args: &[&str]) -> Result<Output, String> {
+    // 実行中は更新で実行ファイルが入れ替わらないようにする
+    let _executable = app_handle.state::<YtDlpManager>().acquire_executable().await;
+    app_handle.shell()
+        .sidecar(get_executable_path(app_handle).map_err(|e| format!("Failed to get executable path: {}", e))?)
+        .map_err(|e| format!("Failed to execute yt-dlp: {}", e))?
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| format!("Failed to execute yt-dlp: {}", e))
+}
+
+
+// --- 公開関数 ---
+
+/// yt-dlpのエラー出力が、更新で直る種類の抽出エラーか
+pub fn is_outdated_extractor_error(stderr: &str) -> bool {
+    let stderr = stderr.to_lowercase();
+    OUTDATED_EXTRACTOR_PATTERNS.iter().any(|pattern| stderr.contains(pattern))
+}
+
+/// yt-dlpを実行して出力を返す
+///
+/// 抽出エラーで失敗したときは更新を試し、新しいバージョンが入ったら1回だけやり直す。
+/// ダウンロード中は入れ替えがダウンロードの終了を待つので、更新は裏で進めてやり直さない。
+/// 終了コードは呼び出し側で確認する。
+pub async fn run_ytdlp(app_handle: &AppHandle, args: &[&str]) -> Result<Output, String> {
+    let output = run_once(app_handle, args).await?;
+    if output.status.success() || !is_outdated_extractor_error(&String::from_utf8_lossy(&output.stderr)) {
+        return Ok(output);
+    }
+    if app_handle.state::<DownloadManager>().has_active() {
+        let app_handle = app_handle.clone();
+        tauri::async_runtime::spawn(async move {
+            update_after_extractor_error(&app_handle).await;
+        });
+        return Ok(output);
+    }
+    if !update_after_extractor_error(app_handle).await {
+        return Ok(output);
+    }
+    println!("yt-dlpを更新したのでやり直します");
+    run_once(app_handle, args).await
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_outdated_extractor_errors() {
+        assert!(is_outdated_extractor_error(
+            "ERROR: [youtube] dQw4w9WgXcQ: Failed to extract any player response; please report this issue on  https://github.com/yt-dlp/yt-dlp/issues?q= , filling out the appropriate issue template. Confirm you are on the latest version using  yt-dlp -U",
+        ));
+        assert!(is_outdated_extractor_error(
+            "WARNING: [youtube] dQw4w9WgXcQ: nsig extraction failed: Some formats may be missing",
+        ));
+        assert!(is_outdated_extractor_error(
+            "ERROR: [youtube] dQw4w9WgXcQ: Signature extraction failed: Some formats may be missing",
+        ));
+    }
+
+    #[test]
+    fn ignores_expected_errors() {
+        assert!(!is_outdated_extractor_error(
+            "ERROR: [youtube] dQw4w9WgXcQ: Unable to download API page: <urlopen error [Errno -3] Temporary failure in name resolution> (caused by URLError(gaierror(-3, 'Temporary failure in name resolution')))",
+        ));
+        assert!(!is_outdated_extractor_error(
+            "ERROR: [youtube] dQw4w9WgXcQ: Video unavailable. The uploader has not made this video available in your country",
+        ));
+        assert!(!is_outdated_extractor_error("ERROR: [youtube] dQw4w9WgXcQ: Private video. Sign in if you've been granted access to this video"));
+        assert!(!is_outdated_extractor_error("ERROR: [youtube] dQw4w9WgXcQ: Unable to extract initial data; Sign in to confirm you're not a bot"));
+        assert!(!is_outdated_extractor_error(""));
+    }
+}
@@ -0,0 +1,397 @@
+use base64::Engine;
+use rsa::{BigUint, Pkcs1v15Sign, RsaPublicKey};
+use sha2::{Digest, Sha224, Sha256, Sha384, Sha512};
+use anyhow::{Result, anyhow};
+
+// --- 定数定義 ---
+/// yt-dlpのリリースに署名している公開鍵
+///
+/// yt-dlpのリポジトリの `public.key` をそのまま置く。空のままだと更新は署名の検証で失敗する。
+const YTDLP_PUBLIC_KEY: &str = include_str!("../../keys/yt-dlp-public.asc");
+
+// OpenPGP (RFC 4880) のパケットとアルゴリズムの番号
+const TAG_SIGNATURE: u8 = 2;
+const TAG_PUBLIC_KEY: u8 = 6;
+const TAG_PUBLIC_SUBKEY: u8 = 14;
+const SIG_TYPE_BINARY: u8 = 0x00;
+const SIG_TYPE_TEXT: u8 = 0x01;
+const ALGO_RSA: u8 = 1;
+const ALGO_RSA_SIGN_ONLY: u8 = 3;
+const HASH_SHA256: u8 = 8;
+const HASH_SHA384: u8 = 9;
+const HASH_SHA512: u8 = 10;
+const HASH_SHA224: u8 = 11;
+
+
+// --- 型定義 ---
+
+/// 署名の検証に使う公開鍵 (主鍵と副鍵のうちRSAのもの)
+#[derive(Debug, Clone, Default)]
+pub struct TrustedKeys {
+    keys: Vec<RsaPublicKey>,
+}
+
+/// v4の署名パケットのうち検証に使う部分
+struct Signature {
+    sig_type: u8,
+    hash_algo: u8,
+    /// ハッシュの対象になる、バージョンからハッシュ対象のサブパケットまで
+    hashed_part: Vec<u8>,
+    /// ハッシュ値の先頭2バイト (鍵を使う前の確認用)
+    hash_prefix: [u8; 2],
+    value: Vec<u8>,
+}
+
+
+// --- プライベートヘルパー関数 ---
+
+/// ASCII形式 (`-----BEGIN PGP ...`) ならバイナリに戻す。バイナリならそのまま返す
+fn dearmor(data: &[u8]) -> Result<Vec<u8>> {
+    let Ok(text) = std::str::from_utf8(data) else { return Ok(data.to_vec()) };
+    let Some(start) = text.find("-----BEGIN PGP ") else { return Ok(data.to_vec()) };
+
+    let mut lines = text[start..].lines().skip(1);
+    // ヘッダー行 ("Version: ..." など) は空行まで
+    let mut body = String::new();
+    let mut in_headers = true;
+    for line in lines.by_ref() {
+        let line = line.trim();
+        if line.starts_with("-----END PGP ") {
+            break;
+        }
+        if in_headers {
+            if line.is_empty() {
+                in_headers = false;
+            } else if !line.contains(": ") {
+                // ヘッダーの無いファイル
+                in_headers = false;
+                body.push_str(line);
+            }
+            continue;
+        }
+        // "=" で始まる行はCRC24のチェックサム
+        if line.starts_with('=') {
+            break;
+        }
+        body.push_str(line);
+    }
+    base64::engine::general_purpose::STANDARD.decode(body.as_bytes())
+        .map_err(|e| anyhow!("ASCII形式の解析に失敗: {}", e))
+}
+
+/// パケットを (タグ, 本体) に分ける
+fn read_packets(mut data: &[u8]) -> Result<Vec<(u8, &[u8])>> {
+    let truncated = || anyhow!("OpenPGPのデータが途中で切れています");
+    let mut packets = Vec::new();
+
+    while let Some((&header, rest)) = data.split_first() {
+        if header & 0x80 == 0 {
+            return Err(anyhow!("OpenPGPのデータではありません"));
+        }
+        let (tag, len, rest) = if header & 0x40 != 0 {
+            // 新しい形式
+            let tag = header & 0x3f;
+            let (&first, rest) = rest.split_first().ok_or_else(truncated)?;
+            match first {
+                0..=191 => (tag, first as usize, rest),
+                192..=223 => {
+                    let (&second, rest) = rest.split_first().ok_or_else(truncated)?;
+                    (tag, ((first as usize - 192) << 8) + second as usize + 192, rest)
+                }
+                255 => {
+                    let bytes = rest.get(..4).ok_or_else(truncated)?;
+                    (tag, u32::from_be_bytes(bytes.try_into()?) as usize, &rest[4..])
+                }
+                _ => return Err(anyhow!("分割された長さのパケットには対応していません")),
+            }
+        } else {
+            // 古い形式
+            let tag = (header >> 2) & 0x0f;
+            match header & 0x03 {
+                0 => {
+                    let (&len, rest) = rest.split_first().ok_or_else(truncated)?;
+                    (tag, len as usize, rest)
+                }
+                1 => {
+                    let bytes = rest.get(..2).ok_or_else(truncated)?;
+                    (tag, u16::from_be_bytes(bytes.try_into()?) as usize, &rest[2..])
+                }
+                2 => {
+                    let bytes = rest.get(..4).ok_or_else(truncated)?;
+                    (tag, u32::from_be_bytes(bytes.try_into()?) as usize, &rest[4..])
+                }
+                _ => (tag, rest.len(), rest),
+            }
+        };
+
+        let body = rest.get(..len).ok_or_else(truncated)?;
+        packets.push((tag, body));
+        data = &rest[len..];
+    }
+    Ok(packets)
+}
+
+/// 多倍長整数 (先頭2バイトがビット数) を読み、残りを返す
+fn read_mpi(data: &[u8]) -> Result<(&[u8], &[u8])> {
+    let bits = data.get(..2).ok_or_else(|| anyhow!("MPIが途中で切れています"))?;
+    let len = (u16::from_be_bytes(bits.try_into()?) as usize).div_ceil(8);
+    let value = data.get(2..2 + len).ok_or_else(|| anyhow!("MPIが途中で切れています"))?;
+    Ok((value, &data[2 + len..]))
+}
+
+/// v4の公開鍵パケットからRSAの鍵を取り出す。RSA以外は None
+fn parse_public_key(body: &[u8]) -> Result<Option<RsaPublicKey>> {
+    // バージョン(1) 作成日時(4) アルゴリズム(1)
+    let (Some(&4), Some(&algo)) = (body.first(), body.get(5)) else { return Ok(None) };
+    if !matches!(algo, ALGO_RSA | ALGO_RSA_SIGN_ONLY) {
+        return Ok(None);
+    }
+    let (n, rest) = read_mpi(&body[6..])?;
+    let (e, _) = read_mpi(rest)?;
+    let key = RsaPublicKey::new(BigUint::from_bytes_be(n), BigUint::from_bytes_be(e))?;
+    Ok(Some(key))
+}
+
+fn parse_signature(body: &[u8]) -> Result<Signature> {
+    let truncated = || anyhow!("署名が途中で切れています");
+    // バージョン(1) 種類(1) 公開鍵アルゴリズム(1) ハッシュアルゴリズム(1) ハッシュ対象の長さ(2)
+    let header = body.get(..6).ok_or_else(truncated)?;
+    if header[0] != 4 {
+        return Err(anyhow!("v4以外の署名には対応していません"));
+    }
+    if !matches!(header[2], ALGO_RSA | ALGO_RSA_SIGN_ONLY) {
+        return Err(anyhow!("RSA以外の署名には対応していません"));
+    }
+    let hashed_end = 6 + u16::from_be_bytes([header[4], header[5]]) as usize;
+    let hashed_part = body.get(..hashed_end).ok_or_else(truncated)?.to_vec();
+
+    let unhashed_len = body.get(hashed_end..hashed_end + 2).ok_or_else(truncated)?;
+    let unhashed_end = hashed_end + 2 + u16::from_be_bytes(unhashed_len.try_into()?) as usize;
+    let prefix = body.get(unhashed_end..unhashed_end + 2).ok_or_else(truncated)?;
+    let (value, _) = read_mpi(&body[unhashed_end + 2..])?;
+
+    Ok(Signature {
+        sig_type: header[1],
+        hash_algo: header[3],
+        hashed_part,
+        hash_prefix: [prefix[0], prefix[1]],
+        value: value.to_vec(),
+    })
+}
+
+/// テキストの署名は改行をCRLFにそろえたものに対して作られる
+fn canonicalize_text(message: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(message.len());
+    for (i, &b) in message.iter().enumerate() {
+        if b == b'\n' && (i == 0 || message[i - 1] != b'\r') {
+            out.push(b'\r');
+        }
+        out.push(b);
+    }
+    out
+}
+
+/// 署名の対象 (本文、署名のハッシュ対象部分、末尾の長さ) のハッシュと、検証の方式を返す
+fn signed_digest(signature: &Signature, message: &[u8]) -> Result<(Vec<u8>, Pkcs1v15Sign)> {
+    fn digest<D: Digest>(parts: &[&[u8]]) -> Vec<u8> {
+        let mut hasher = D::new();
+        for part in parts {
+            hasher.update(part);
+        }
+        hasher.finalize().to_vec()
+    }
+
+    let message = match signature.sig_type {
+        SIG_TYPE_BINARY => message.to_vec(),
+        SIG_TYPE_TEXT => canonicalize_text(message),
+        other => return Err(anyhow!("ファイルの署名ではありません (種類 {:#04x})", other)),
+    };
+    let mut trailer = vec![4, 0xff];
+    trailer.extend_from_slice(&(signature.hashed_part.len() as u32).to_be_bytes());
+    let parts: [&[u8]; 3] = [&message, &signature.hashed_part, &trailer];
+
+    Ok(match signature.hash_algo {
+        HASH_SHA256 => (digest::<Sha256>(&parts), Pkcs1v15Sign::new::<Sha256>()),
+        HASH_SHA384 => (digest::<Sha384>(&parts), Pkcs1v15Sign::new::<Sha384>()),
+        HASH_SHA512 => (digest::<Sha512>(&parts), Pkcs1v15Sign::new::<Sha512>()),
+        HASH_SHA224 => (digest::<Sha224>(&parts), Pkcs1v15Sign::new::<Sha224>()),
+        other => return Err(anyhow!("対応していないハッシュアルゴリズムです ({})", other)),
+    })
+}
+
+
+// --- 公開関数 ---
+
+impl TrustedKeys {
+    /// 組み込みのyt-dlpの公開鍵
+    pub fn ytdlp() -> Result<Self> {
+        Self::parse(YTDLP_PUBLIC_KEY.as_bytes())
+    }
+
+    /// 公開鍵 (ASCII形式・バイナリのどちらでもよい) を読む
+    ///
+    /// 空のデータは鍵が無いものとして扱い、中身があるのに使える鍵が無ければエラーにする。
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        if data.trim_ascii().is_empty() {
+            return Ok(Self::default());
+        }
+        let data = dearmor(data)?;
+        let mut keys = Vec::new();
+        for (tag, body) in read_packets(&data)? {
+            if matches!(tag, TAG_PUBLIC_KEY | TAG_PUBLIC_SUBKEY) {
+                keys.extend(parse_public_key(body)?);
+            }
+        }
+        if keys.is_empty() {
+            return Err(anyhow!("RSAの公開鍵が含まれていません"));
+        }
+        Ok(Self { keys })
+    }
+
+    pub fn extend(&mut self, other: TrustedKeys) {
+        self.keys.extend(other.keys);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// 分離署名 (`.sig`) を検証する。どれかの署名がどれかの鍵で検証できれば成功
+    ///
+    /// 対応していない形式の署名は飛ばし、検証できるものが無いときだけその理由を返す。
+    pub fn verify_detached(&self, message: &[u8], signature: &[u8]) -> Result<()> {
+        if self.keys.is_empty() {
+            return Err(anyhow!("署名を検証する公開鍵がありません"));
+        }
+        let data = dearmor(signature)?;
+        let packets = read_packets(&data)?;
+        let signatures: Vec<&[u8]> = packets.iter()
+            .filter(|(tag, _)| *tag == TAG_SIGNATURE)
+            .map(|(_, body)| *body)
+            .collect();
+        if signatures.is_empty() {
+            return Err(anyhow!("署名が含まれていません"));
+        }
+
+        let mut unsupported = None;
+        let mut checked = false;
+        for body in signatures {
+            let parsed = parse_signature(body)
+                .and_then(|signature| signed_digest(&signature, message).map(|digest| (signature, digest)));
+            let (signature, (digest, scheme)) = match parsed {
+                Ok(parsed) => parsed,
+                Err(e) => {
+                    unsupported = Some(e);
+                    continue;
+                }
+            };
+            checked = true;
+            if digest[..2] != signature.hash_prefix {
+                continue;
+            }
+            for key in &self.keys {
+                // 署名値は先頭の0が省かれているので鍵の長さにそろえる
+                let size = rsa::traits::PublicKeyParts::size(key);
+                if signature.value.len() > size {
+                    continue;
+                }
+                let mut value = vec![0; size - signature.value.len()];
+                value.extend_from_slice(&signature.value);
+                if key.verify(scheme.clone(), &digest, &value).is_ok() {
+                    return Ok(());
+                }
+            }
+        }
+        match unsupported {
+            Some(e) if !checked => Err(e),
+            _ => Err(anyhow!("信頼できる鍵による署名ではありません")),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // gpgで作った鍵で SHA2-256SUMS に署名したもの
+    const SUMS: &[u8] = include_bytes!("../../tests/fixtures/SHA2-256SUMS");
+    const SUMS_SIG: &[u8] = include_bytes!("../../tests/fixtures/SHA2-256SUMS.sig");
+    const SUMS_ASC: &[u8] = include_bytes!("../../tests/fixtures/SHA2-256SUMS.asc");
+    const SIGNING_KEY: &[u8] = include_bytes!("../../tests/fixtures/ytdlp-test-key.asc");
+    const OTHER_KEY: &[u8] = include_bytes!("../../tests/fixtures/other-key.asc");
+
+    fn signing_key() -> TrustedKeys {
+        TrustedKeys::parse(SIGNING_KEY).unwrap()
+    }
+
+    #[test]
+    fn accepts_good_signature() {
+        signing_key().verify_detached(SUMS, SUMS_SIG).unwrap();
+        signing_key().verify_detached(SUMS, SUMS_ASC).unwrap();
+    }
+
+    #[test]
+    fn accepts_when_any_trusted_key_matches() {
+        let mut keys = TrustedKeys::parse(OTHER_KEY).unwrap();
+        keys.extend(signing_key());
+        keys.verify_detached(SUMS, SUMS_SIG).unwrap();
+    }
+
+    #[test]
+    fn rejects_tampered_file() {
+        let mut tampered = SUMS.to_vec();
+        tampered[0] ^= 0x01;
+        assert!(signing_key().verify_detached(&tampered, SUMS_SIG).is_err());
+
+        let mut appended = SUMS.to_vec();
+        appended.extend_from_slice(b"0000000000000000000000000000000000000000000000000000000000000000  yt-dlp_evil\n");
+        assert!(signing_key().verify_detached(&appended, SUMS_SIG).is_err());
+    }
+
+    #[test]
+    fn rejects_wrong_key() {
+        let other = TrustedKeys::parse(OTHER_KEY).unwrap();
+        assert!(other.verify_detached(SUMS, SUMS_SIG).is_err());
+        assert!(TrustedKeys::default().verify_detached(SUMS, SUMS_SIG).is_err());
+    }
+
+    #[test]
+    fn rejects_truncated_signature() {
+        for len in [0, 1, 10, SUMS_SIG.len() / 2, SUMS_SIG.len() - 1] {
+            assert!(signing_key().verify_detached(SUMS, &SUMS_SIG[..len]).is_err(), "length {}", len);
+        }
+    }
+
+    #[test]
+    fn skips_unsupported_signature_packets() {
+        // DSAの署名パケット (新しい形式のヘッダー) を前に置く
+        let dsa_body = [4, SIG_TYPE_BINARY, 17, HASH_SHA256, 0, 0, 0, 0, 0xab, 0xcd, 0, 8, 0xff];
+        let mut signature = vec![0xc0 | TAG_SIGNATURE, dsa_body.len() as u8];
+        signature.extend_from_slice(&dsa_body);
+
+        assert!(signing_key().verify_detached(SUMS, &signature).is_err());
+
+        signature.extend_from_slice(SUMS_SIG);
+        signing_key().verify_detached(SUMS, &signature).unwrap();
+    }
+
+    #[test]
+    fn parses_empty_key_as_no_keys() {
+        assert!(TrustedKeys::parse(b"").unwrap().is_empty());
+        assert!(TrustedKeys::parse(b"\n").unwrap().is_empty());
+        assert!(!signing_key().is_empty());
+    }
+
+    #[test]
+    fn rejects_key_data_without_rsa_keys() {
+        assert!(TrustedKeys::parse(b"not a key").is_err());
+        assert!(TrustedKeys::parse(SUMS_SIG).is_err());
+    }
+
+    #[test]
+    fn bundled_ytdlp_key_is_present() {
+        // keys/yt-dlp-public.asc が空のままだと更新時の署名検証がすべて失敗する
+        assert!(!TrustedKeys::ytdlp().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,196 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use serde::Serialize;
+use futures::StreamExt;
+use reqwest::{header, StatusCode};
+use anyhow::{Result, Context, anyhow};
+
+// --- 定数定義 ---
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+/// この間データが届かなければ切断とみなす
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+/// 途中で切れたときに続きから取り直す回数
+const MAX_ATTEMPTS: u32 = 4;
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+/// 進捗を知らせる間隔
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+/// 速度の計算に使う直近の区間
+const THROUGHPUT_WINDOW: Duration = Duration::from_secs(3);
+const PART_SUFFIX: &str = "part";
+
+
+// --- 型定義 ---
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TransferProgress {
+    pub file_name: String,
+    pub downloaded_bytes: u64,
+    pub total_bytes: Option<u64>,
+    /// バイト/秒 (直近の数秒の平均)
+    pub bytes_per_second: u64,
+    /// 秒
+    pub eta: Option<u64>,
+    /// 途中から再開したバイト数
+    pub resumed_from: u64,
+}
+
+/// 直近の区間の受信量から速度を出す
+struct Throughput {
+    samples: Vec<(Instant, u64)>,
+}
+
+impl Throughput {
+    fn new(downloaded: u64) -> Self {
+        Self { samples: vec![(Instant::now(), downloaded)] }
+    }
+
+    fn record(&mut self, downloaded: u64) -> u64 {
+        let now = Instant::now();
+        self.samples.push((now, downloaded));
+        // 窓より古いものは1件だけ残して基準にする
+        while self.samples.len() > 2 && now.duration_since(self.samples[1].0) > THROUGHPUT_WINDOW {
+            self.samples.remove(0);
+        }
+        let (start, start_bytes) = self.samples[0];
+        let elapsed = now.duration_since(start).as_secs_f64();
+        if elapsed <= 0.0 {
+            return 0;
+        }
+        ((downloaded - start_bytes) as f64 / elapsed) as u64
+    }
+}
+
+
+// --- プライベートヘルパー関数 ---
+
+fn part_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}", PART_SUFFIX));
+    path.with_file_name(name)
+}
+
+/// `Content-Range: bytes 100-199/200` から全体の大きさを読む
+fn total_from_content_range(value: &str) -> Option<u64> {
+    value.rsplit('/').next()?.trim().parse().ok()
+}
+
+/// 1回分の取得。`.part` に追記し、最後まで届いたら true
+async fn fetch_once<F: FnMut(&TransferProgress)>(
+    http_client: &reqwest::Client,
+    url: &str,
+    part: &Path,
+    file_name: &str,
+    on_progress: &mut F,
+) -> Result<bool> {
+    let existing = std::fs::metadata(part).map(|m| m.len()).unwrap_or(0);
+    let mut request = http_client.get(url);
+    if existing > 0 {
+        request = request.header(header::RANGE, format!("bytes={}-", existing));
+    }
+    let response = request.send().await?;
+
+    let status = response.status();
+    if status == StatusCode::RANGE_NOT_SATISFIABLE {
+        // 手元の `.part` が合わないので最初から取り直す
+        std::fs::remove_file(part)?;
+        return Ok(false);
+    }
+    let response = response.error_for_status()?;
+
+    // 206なら続きから、200ならサーバーがRangeに対応していないので最初から
+    let resumed_from = if status == StatusCode::PARTIAL_CONTENT { existing } else { 0 };
+    let total_bytes = if status == StatusCode::PARTIAL_CONTENT {
+        response.headers().get(header::CONTENT_RANGE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(total_from_content_range)
+    } else {
+        response.content_length()
+    };
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(resumed_from > 0)
+        .truncate(resumed_from == 0)
+        .open(part)?;
+
+    let mut downloaded = resumed_from;
+    let mut throughput = Throughput::new(downloaded);
+    let mut last_report: Option<Instant> = None;
+    let mut stream = response.bytes_stream();
+
+    loop {
+        let chunk = tokio::time::timeout(READ_TIMEOUT, stream.next()).await
+            .map_err(|_| anyhow!("{}秒間データが届きませんでした", READ_TIMEOUT.as_secs()))?;
+        let Some(chunk) = chunk else { break };
+        let chunk = chunk?;
+        file.write_all(&chunk)?;
+        downloaded += chunk.len() as u64;
+
+        let finished = total_bytes.is_some_and(|t| downloaded >= t);
+        if last_report.is_none_or(|t| t.elapsed() >= PROGRESS_INTERVAL) || finished {
+            let bytes_per_second = throughput.record(downloaded);
+            let eta = total_bytes
+                .filter(|_| bytes_per_second > 0)
+                .map(|t| t.saturating_sub(downloaded) / bytes_per_second);
+            on_progress(&TransferProgress {
+                file_name: file_name.to_string(),
+                downloaded_bytes: downloaded,
+                total_bytes,
+                bytes_per_second,
+                eta,
+                resumed_from,
+            });
+            last_report = Some(Instant::now());
+        }
+    }
+    file.flush()?;
+
+    match total_bytes {
+        Some(total) if downloaded < total => Err(anyhow!("接続が途中で切れました ({}/{} バイト)", downloaded, total)),
+        _ => Ok(true),
+    }
+}
+
+
+// --- 公開関数 ---
+
+/// `url` を `path` にダウンロードする
+///
+/// `.part` に少しずつ書き込み、切れたときや前回の残りがあるときはRangeで続きから取る。
+/// 進捗は `PROGRESS_INTERVAL` ごとに `on_progress` で知らせる。
+pub async fn download_resumable<F: FnMut(&TransferProgress)>(
+    http_client: &reqwest::Client,
+    url: &str,
+    path: &Path,
+    mut on_progress: F,
+) -> Result<()> {
+    let part = part_path(path);
+    let file_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+
+    let mut last_error = None;
+    for attempt in 1..=MAX_ATTEMPTS {
+        match fetch_once(http_client, url, &part, &file_name, &mut on_progress).await {
+            Ok(true) => {
+                std::fs::rename(&part, path).context("ダウンロードしたファイルの移動に失敗")?;
+                return Ok(());
+            }
+            // `.part` を消したので、すぐに最初から取り直す
+            Ok(false) => continue,
+            Err(e) => {
+                // 404などは取り直しても変わらない
+                let client_error = e.downcast_ref::<reqwest::Error>()
+                    .and_then(reqwest::Error::status)
+                    .is_some_and(|s| s.is_client_error());
+                if client_error {
+                    return Err(e);
+                }
+                println!("Download of {} failed (attempt {}/{}): {}", file_name, attempt, MAX_ATTEMPTS, e);
+                last_error = Some(e);
+                tokio::time::sleep(RETRY_DELAY).await;
+            }
+        }
+    }
+    Err(last_error.unwrap_or_else(|| anyhow!("ダウンロードに失敗しました")))
+}
@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+
+
+// --- 型定義 ---
+
+/// yt-dlpの日付形式のバージョン (`2024.03.10`, `2024.03.10.1`, nightlyの `2025.01.15.232803` など)
+///
+/// 4つ目の数字は同じ日の追加リリース、またはnightly・masterのビルド時刻で、無ければ0として比べる。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct YtDlpVersion {
+    pub year: u32,
+    pub month: u32,
+    pub day: u32,
+    pub revision: u64,
+}
+
+impl YtDlpVersion {
+    /// タグやバージョンファイルの文字列を読む。日付形式でなければ None
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let text = text.strip_prefix('v').unwrap_or(text);
+        let mut parts = text.split('.');
+
+        let year: u32 = parts.next()?.parse().ok()?;
+        let month: u32 = parts.next()?.parse().ok()?;
+        let day: u32 = parts.next()?.parse().ok()?;
+        let revision: u64 = match parts.next() {
+            Some(part) => part.parse().ok()?,
+            None => 0,
+        };
+        if parts.next().is_some() || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            return None;
+        }
+        Some(Self { year, month, day, revision })
+    }
+}
+
+/// 更新に使うリリースの系統 (それぞれ別のリポジトリで配布されている)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    Nightly,
+    Master,
+}
+
+impl UpdateChannel {
+    pub fn repo(&self) -> &'static str {
+        match self {
+            UpdateChannel::Stable => "yt-dlp/yt-dlp",
+            UpdateChannel::Nightly => "yt-dlp/yt-dlp-nightly-builds",
+            UpdateChannel::Master => "yt-dlp/yt-dlp-master-builds",
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(text: &str) -> YtDlpVersion {
+        YtDlpVersion::parse(text).unwrap_or_else(|| panic!("failed to parse {}", text))
+    }
+
+    #[test]
+    fn parses_date_versions() {
+        assert_eq!(version("2024.03.10"), YtDlpVersion { year: 2024, month: 3, day: 10, revision: 0 });
+        assert_eq!(version("2024.03.10.1"), YtDlpVersion { year: 2024, month: 3, day: 10, revision: 1 });
+        assert_eq!(version(" 2024.03.10\n"), version("2024.03.10"));
+    }
+
+    #[test]
+    fn accepts_v_prefix() {
+        assert_eq!(version("v2024.03.10"), version("2024.03.10"));
+        assert_eq!(version("v2025.01.15.232803"), version("2025.01.15.232803"));
+    }
+
+    #[test]
+    fn orders_same_day_releases_by_revision() {
+        assert!(version("2024.03.10") < version("2024.03.10.1"));
+        assert!(version("2024.03.10.1") < version("2024.03.10.2"));
+        // 文字列としての比較とは逆になる
+        assert!(version("2024.03.10.9") < version("2024.03.10.10"));
+    }
+
+    #[test]
+    fn orders_nightly_after_stable_of_same_day() {
+        let stable = version("2025.01.15");
+        let nightly = version("2025.01.15.232803");
+        assert!(stable < nightly);
+        assert!(nightly < version("2025.01.16"));
+        assert!(version("2024.12.31.235959") < stable);
+    }
+
+    #[test]
+    fn orders_by_date_before_revision() {
+        assert!(version("2023.12.30") < version("2024.01.01"));
+        assert!(version("2024.02.29.99") < version("2024.03.01"));
+        assert!(version("2024.9.1") < version("2024.10.1"));
+    }
+
+    #[test]
+    fn rejects_invalid_versions() {
+        assert_eq!(YtDlpVersion::parse("2024.13.01"), None);
+        assert_eq!(YtDlpVersion::parse("2024.00.10"), None);
+        assert_eq!(YtDlpVersion::parse("2024.03.32"), None);
+        assert_eq!(YtDlpVersion::parse("2024.03.00"), None);
+        assert_eq!(YtDlpVersion::parse("2024.03.10.1.2"), None);
+        assert_eq!(YtDlpVersion::parse("2024.03"), None);
+        assert_eq!(YtDlpVersion::parse("2024.03.10-dev"), None);
+        assert_eq!(YtDlpVersion::parse("2024.03.10."), None);
+        assert_eq!(YtDlpVersion::parse("vv2024.03.10"), None);
+        assert_eq!(YtDlpVersion::parse("stable@2024.03.10"), None);
+        assert_eq!(YtDlpVersion::parse(""), None);
+    }
+}
@@ -0,0 +1,166 @@
+use tauri::{AppHandle, Manager, State};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use serde::{Deserialize, Serialize};
+use anyhow::{Result, Context, anyhow};
+
+use crate::download::manager::DownloadManager;
+use crate::library::index::Library;
+
+// --- 定数定義 ---
+const ARCHIVE_FILE_NAME: &str = "download-archive.txt";
+
+// 書き換え中に読み込みや追記が混ざらないようにする
+static ARCHIVE_LOCK: Mutex<()> = Mutex::new(());
+
+
+// --- 型定義 ---
+
+/// yt-dlpの `--download-archive` の1行 ("youtube <動画ID>")
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct ArchiveEntry {
+    pub extractor: String,
+    pub id: String,
+}
+
+impl ArchiveEntry {
+    fn parse(line: &str) -> Option<Self> {
+        let mut parts = line.split_whitespace();
+        let extractor = parts.next()?.to_lowercase();
+        let id = parts.next()?.to_string();
+        Some(Self { extractor, id })
+    }
+
+    fn to_line(&self) -> String {
+        format!("{} {}", self.extractor, self.id)
+    }
+}
+
+/// アーカイブから削除するもの
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PruneTarget {
+    /// 指定した動画ID
+    Ids(Vec<String>),
+    /// ライブラリにファイルが無いもの
+    MissingFiles,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArchiveImportResult {
+    pub added: usize,
+    pub skipped: usize,
+}
+
+
+// --- 公開関数 ---
+
+/// アーカイブファイルのパス (yt-dlpにもこのパスを渡す)
+pub fn get_archive_path(app_handle: &AppHandle) -> Result<PathBuf> {
+    let app_data_dir = app_handle.path().app_data_dir().context("アプリデータディレクトリの取得に失敗")?;
+    Ok(app_data_dir.join(ARCHIVE_FILE_NAME))
+}
+
+
+// --- プライベートヘルパー関数 ---
+
+fn parse_archive(content: &str) -> Vec<ArchiveEntry> {
+    content.lines().filter_map(ArchiveEntry::parse).collect()
+}
+
+/// アプリのアーカイブを読む。まだ何もダウンロードしていなければファイルが無いので空とみなす
+fn read_archive(path: &Path) -> Result<Vec<ArchiveEntry>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    Ok(parse_archive(&std::fs::read_to_string(path)?))
+}
+
+fn write_archive(path: &Path, entries: &[ArchiveEntry]) -> Result<()> {
+    let content: String = entries.iter().map(|e| e.to_line() + "\n").collect();
+    let temp_path = path.with_extension("txt.tmp");
+    std::fs::write(&temp_path, content)?;
+    std::fs::rename(&temp_path, path)?;
+    Ok(())
+}
+
+/// yt-dlpが追記している最中に書き換えないよう、実行中のダウンロードがあれば拒否する
+/// (書き換えが終わるまで新しいダウンロードも始まらない)
+fn while_no_active_downloads<T>(manager: &DownloadManager, f: impl FnOnce() -> Result<T>) -> Result<T> {
+    manager.run_while_idle(f)
+        .ok_or_else(|| anyhow!("ダウンロード中はアーカイブを変更できません"))?
+}
+
+/// 他のyt-dlpやアプリで作ったアーカイブを取り込む (重複は除く)
+fn import_archive(archive_path: &Path, source: &Path) -> Result<ArchiveImportResult> {
+    let _guard = ARCHIVE_LOCK.lock().unwrap();
+    let mut entries = read_archive(archive_path)?;
+    let mut known: HashSet<ArchiveEntry> = entries.iter().cloned().collect();
+
+    let mut result = ArchiveImportResult { added: 0, skipped: 0 };
+    // 取り込み元が無いのは指定の誤りなので、空として成功にしない
+    let source_content = std::fs::read_to_string(source)
+        .with_context(|| format!("アーカイブを読めません: {}", source.display()))?;
+    for entry in parse_archive(&source_content) {
+        if known.insert(entry.clone()) {
+            entries.push(entry);
+            result.added += 1;
+        } else {
+            result.skipped += 1;
+        }
+    }
+
+    write_archive(archive_path, &entries)?;
+    Ok(result)
+}
+
+fn prune_archive(archive_path: &Path, library: &Library, target: PruneTarget) -> Result<usize> {
+    let _guard = ARCHIVE_LOCK.lock().unwrap();
+    let entries = read_archive(archive_path)?;
+    let before = entries.len();
+
+    let kept: Vec<ArchiveEntry> = match target {
+        PruneTarget::Ids(ids) => entries.into_iter().filter(|e| !ids.contains(&e.id)).collect(),
+        PruneTarget::MissingFiles => entries.into_iter()
+            .filter(|e| e.extractor != "youtube" || library.find(&e.id).is_some())
+            .collect(),
+    };
+
+    write_archive(archive_path, &kept)?;
+    Ok(before - kept.len())
+}
+
+
+// --- Tauri Commands ---
+
+#[tauri::command]
+pub fn get_download_archive(app_handle: AppHandle) -> Result<Vec<ArchiveEntry>, String> {
+    let path = get_archive_path(&app_handle).map_err(|e| e.to_string())?;
+    let _guard = ARCHIVE_LOCK.lock().unwrap();
+    read_archive(&path).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn import_download_archive(
+    app_handle: AppHandle,
+    manager: State<'_, DownloadManager>,
+    source_path: String,
+) -> Result<ArchiveImportResult, String> {
+    let path = get_archive_path(&app_handle).map_err(|e| e.to_string())?;
+    while_no_active_downloads(&manager, || import_archive(&path, Path::new(&source_path)))
+        .map_err(|e| e.to_string())
+}
+
+/// 削除した件数を返す
+#[tauri::command]
+pub fn prune_download_archive(
+    app_handle: AppHandle,
+    manager: State<'_, DownloadManager>,
+    library: State<'_, Library>,
+    target: PruneTarget,
+) -> Result<usize, String> {
+    let path = get_archive_path(&app_handle).map_err(|e| e.to_string())?;
+    while_no_active_downloads(&manager, || prune_archive(&path, &library, target))
+        .map_err(|e| e.to_string())
+}
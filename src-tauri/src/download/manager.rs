@@ -0,0 +1,609 @@
+use tauri::{AppHandle, Manager, Emitter, State};
+use tauri_plugin_shell::ShellExt;
+use tauri_plugin_shell::process::{CommandEvent, CommandChild};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use anyhow::{Result, Context, anyhow};
+use encoding_rs::SHIFT_JIS;
+use tokio::sync::OwnedRwLockReadGuard;
+
+use crate::dlp::dlp_manager::{update_after_extractor_error, YtDlpManager};
+use crate::dlp::runner::is_outdated_extractor_error;
+use crate::dlp::get_channel::get_executable_path;
+use crate::library::index::Library;
+use crate::download::archive::get_archive_path;
+use crate::download::options::{DownloadOptions, DownloadPreset};
+use crate::download::progress::{parse_line, DownloadProgress, OutputLine};
+
+// --- 定数定義 ---
+const QUEUE_FILE_NAME: &str = "downloads.json";
+const MAX_CONCURRENT_DOWNLOADS: usize = 2;
+const DEFAULT_MAX_RETRIES: u32 = 3;
+
+#[cfg(target_os = "windows")]
+const FFMPEG_EXECUTABLE_NAME: &str = "ffmpeg.exe";
+#[cfg(not(target_os = "windows"))]
+const FFMPEG_EXECUTABLE_NAME: &str = "ffmpeg";
+
+
+// --- 型定義 ---
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DownloadStatus {
+    Queued,
+    Downloading,
+    Paused,
+    Completed,
+    Failed,
+    Canceled,
+}
+
+/// ダウンロードの指定内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadRequest {
+    pub video_url: String,
+    pub output_path: String,
+    /// 指定した場合は options のフォーマット選択より優先する
+    pub format_id: Option<String>,
+    #[serde(default)]
+    pub options: DownloadOptions,
+    /// 自動ダウンロードのルールで追加したとき true (残す件数の対象にする)
+    #[serde(default)]
+    pub from_rule: bool,
+}
+
+impl DownloadRequest {
+    /// プリセットまたは個別オプションからリクエストを作る (個別オプションを優先)
+    pub fn new(
+        video_url: String,
+        output_path: String,
+        format_id: Option<String>,
+        preset: Option<DownloadPreset>,
+        options: Option<DownloadOptions>,
+    ) -> Self {
+        let options = options
+            .or_else(|| preset.map(|p| p.options()))
+            .unwrap_or_default();
+        Self { video_url, output_path, format_id, options, from_rule: false }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadJob {
+    pub id: String,
+    pub request: DownloadRequest,
+    pub status: DownloadStatus,
+    pub progress: DownloadProgress,
+    /// 失敗して再試行した回数
+    pub retries: u32,
+    pub max_retries: u32,
+    pub error: Option<String>,
+    /// yt-dlpが書き出したファイル (中間ファイルを含む)
+    pub files: Vec<String>,
+    /// ダウンロードアーカイブに記録済みでスキップされた
+    #[serde(default)]
+    pub skipped: bool,
+    pub created_at: u64,
+    pub finished_at: Option<u64>,
+}
+
+/// 実行中のyt-dlpプロセス
+struct RunningChild {
+    /// 一時停止直後に再開した場合など、古いプロセスの終了通知を区別するための番号
+    run: u64,
+    /// `kill` でハンドルを使い切ったら None
+    child: Option<CommandChild>,
+}
+
+struct ManagerState {
+    jobs: Vec<DownloadJob>,
+    children: HashMap<String, RunningChild>,
+    next_run: u64,
+}
+
+/// 永続化されたキューを持つダウンロードマネージャー
+pub struct DownloadManager {
+    app_handle: AppHandle,
+    queue_path: PathBuf,
+    state: Mutex<ManagerState>,
+    /// 抽出エラーでyt-dlpを更新している間は新しいジョブを始めない
+    waiting_for_update: AtomicBool,
+}
+
+
+// --- プライベートヘルパー関数 ---
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// Windowsではコンソールのコードページで出力されるため、UTF-8でなければShift_JISとして読む
+fn decode_line(bytes: &[u8]) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(s) => s.to_string(),
+        Err(_) => SHIFT_JIS.decode(bytes).0.into_owned(),
+    }
+}
+
+/// yt-dlpに渡す引数を組み立てる
+pub fn build_download_args(
+    request: &DownloadRequest,
+    ffmpeg_location: Option<&Path>,
+    archive_path: Option<&Path>,
+) -> Vec<String> {
+    let format = request.format_id.clone().unwrap_or_else(|| request.options.format_selector());
+    let mut args = vec![
+        "--cookies-from-browser".to_string(),
+        "firefox".to_string(),
+        "--newline".to_string(),
+        // ライブラリの索引を作り直せるように動画情報を横に保存する
+        "--write-info-json".to_string(),
+        "--output".to_string(),
+        request.output_path.clone(),
+        "--format".to_string(),
+        format,
+    ];
+
+    // チャンネルやプレイリストを再投入しても取得済みの動画は飛ばす
+    if let Some(archive) = archive_path {
+        args.push("--download-archive".to_string());
+        args.push(archive.to_string_lossy().to_string());
+    }
+    if let Some(ffmpeg) = ffmpeg_location {
+        args.push("--ffmpeg-location".to_string());
+        args.push(ffmpeg.to_string_lossy().to_string());
+    }
+    args.extend(request.options.postprocess_args(&request.output_path));
+
+    args.push(request.video_url.clone());
+    args
+}
+
+/// アプリデータディレクトリにffmpegが置かれていればそれを使う (無ければPATHから探される)
+fn find_ffmpeg(app_handle: &AppHandle) -> Option<PathBuf> {
+    let path = app_handle.path().app_data_dir().ok()?.join(FFMPEG_EXECUTABLE_NAME);
+    path.exists().then_some(path)
+}
+
+/// プロセスを止めてから管理対象から外す
+///
+/// 止められなければジョブの枠を持ったまま管理を続け、終了通知で片付ける。
+/// `kill` はハンドルを消費するため、失敗後にもう一度呼ばれたときは止まったものとして外す。
+fn stop_child(children: &mut HashMap<String, RunningChild>, id: &str) -> Result<()> {
+    let Some(running) = children.get_mut(id) else { return Ok(()) };
+    if let Some(child) = running.child.take() {
+        child.kill().map_err(|e| anyhow!("yt-dlpを停止できません: {}", e))?;
+    }
+    children.remove(id);
+    Ok(())
+}
+
+/// キャンセル時に途中のファイル (.part / .ytdl / 断片) を削除する
+fn remove_partial_files(files: &[String]) {
+    for file in files {
+        let path = Path::new(file);
+        for suffix in [".part", ".ytdl"] {
+            let _ = std::fs::remove_file(format!("{}{}", file, suffix));
+        }
+        let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else { continue };
+        let fragment_prefix = format!("{}.part-Frag", name.to_string_lossy());
+        if let Ok(entries) = std::fs::read_dir(parent) {
+            for entry in entries.flatten() {
+                if entry.file_name().to_string_lossy().starts_with(&fragment_prefix) {
+                    let _ = std::fs::remove_file(entry.path());
+                }
+            }
+        }
+    }
+}
+
+
+impl DownloadManager {
+    pub fn new(app_handle: &AppHandle) -> Result<Self> {
+        let app_data_dir = app_handle.path().app_data_dir().context("アプリデータディレクトリの取得に失敗")?;
+        std::fs::create_dir_all(&app_data_dir)?;
+        let queue_path = app_data_dir.join(QUEUE_FILE_NAME);
+
+        let mut jobs: Vec<DownloadJob> = match std::fs::read_to_string(&queue_path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_default(),
+            Err(_) => Vec::new(),
+        };
+
+        // 前回終了時にダウンロード中だったものは .part から再開する
+        for job in jobs.iter_mut().filter(|j| j.status == DownloadStatus::Downloading) {
+            job.status = DownloadStatus::Queued;
+        }
+
+        Ok(Self {
+            app_handle: app_handle.clone(),
+            queue_path,
+            state: Mutex::new(ManagerState { jobs, children: HashMap::new(), next_run: 0 }),
+            waiting_for_update: AtomicBool::new(false),
+        })
+    }
+
+    fn save(&self, jobs: &[DownloadJob]) -> Result<()> {
+        let temp_path = self.queue_path.with_extension("json.tmp");
+        std::fs::write(&temp_path, serde_json::to_string_pretty(jobs)?)?;
+        std::fs::rename(&temp_path, &self.queue_path)?;
+        Ok(())
+    }
+
+    fn emit_status(&self, job: &DownloadJob) {
+        if let Err(e) = self.app_handle.emit("download_status", job) {
+            println!("Failed to emit download status: {}", e);
+        }
+    }
+
+    /// ジョブを変更して保存し、状態変化を通知する
+    fn update_job<F>(&self, id: &str, f: F) -> Result<DownloadJob>
+    where
+        F: FnOnce(&mut DownloadJob, &mut HashMap<String, RunningChild>) -> Result<()>,
+    {
+        let mut state = self.state.lock().unwrap();
+        let ManagerState { jobs, children, .. } = &mut *state;
+        let job = jobs.iter_mut().find(|j| j.id == id).ok_or_else(|| anyhow!("ダウンロードが見つかりません: {}", id))?;
+        f(job, children)?;
+        let job = job.clone();
+        self.save(jobs)?;
+        drop(state);
+
+        self.emit_status(&job);
+        Ok(job)
+    }
+
+    /// 実行中のyt-dlpプロセスがあるか
+    pub fn has_active(&self) -> bool {
+        !self.state.lock().unwrap().children.is_empty()
+    }
+
+    /// 実行中のyt-dlpプロセスが無ければ `f` を実行する。実行中は状態をロックしているので新しいジョブも始まらない
+    pub fn run_while_idle<T>(&self, f: impl FnOnce() -> T) -> Option<T> {
+        let state = self.state.lock().unwrap();
+        state.children.is_empty().then(f)
+    }
+
+    /// 同じURLのジョブが待機中・実行中・一時停止中か
+    pub fn is_queued(&self, video_url: &str) -> bool {
+        self.state.lock().unwrap().jobs.iter().any(|j| {
+            j.request.video_url == video_url
+                && matches!(j.status, DownloadStatus::Queued | DownloadStatus::Downloading | DownloadStatus::Paused)
+        })
+    }
+
+    pub fn list(&self) -> Vec<DownloadJob> {
+        self.state.lock().unwrap().jobs.clone()
+    }
+
+    pub fn enqueue(&self, request: DownloadRequest) -> Result<DownloadJob> {
+        let job = DownloadJob {
+            id: uuid::Uuid::new_v4().to_string(),
+            request,
+            status: DownloadStatus::Queued,
+            progress: DownloadProgress::default(),
+            retries: 0,
+            max_retries: DEFAULT_MAX_RETRIES,
+            error: None,
+            files: Vec::new(),
+            skipped: false,
+            created_at: now_secs(),
+            finished_at: None,
+        };
+
+        {
+            let mut state = self.state.lock().unwrap();
+            state.jobs.push(job.clone());
+            self.save(&state.jobs)?;
+        }
+        self.emit_status(&job);
+        self.pump();
+        Ok(job)
+    }
+
+    /// 空きがあれば待機中のジョブを開始する
+    pub fn pump(&self) {
+        if self.waiting_for_update.load(Ordering::SeqCst) {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        let ManagerState { jobs, children, next_run } = &mut *state;
+        let mut changed = Vec::new();
+
+        for job in jobs.iter_mut() {
+            if children.len() >= MAX_CONCURRENT_DOWNLOADS {
+                break;
+            }
+            if job.status != DownloadStatus::Queued {
+                continue;
+            }
+            // yt-dlpの入れ替え中は待機のままにし、入れ替え後にもう一度呼ばれる
+            let Some(executable_guard) = self.app_handle.state::<YtDlpManager>().try_acquire_executable() else {
+                break;
+            };
+
+            *next_run += 1;
+            match self.spawn(job, *next_run, executable_guard) {
+                Ok(child) => {
+                    children.insert(job.id.clone(), RunningChild { run: *next_run, child: Some(child) });
+                    job.status = DownloadStatus::Downloading;
+                    job.error = None;
+                }
+                Err(e) => {
+                    job.status = DownloadStatus::Failed;
+                    job.error = Some(e.to_string());
+                    job.finished_at = Some(now_secs());
+                }
+            }
+            changed.push(job.clone());
+        }
+
+        if changed.is_empty() {
+            return;
+        }
+        if let Err(e) = self.save(jobs) {
+            println!("Failed to save download queue: {}", e);
+        }
+        drop(state);
+
+        for job in &changed {
+            self.emit_status(job);
+        }
+    }
+
+    /// `executable_guard` はプロセスが終わるまで持ち続ける
+    fn spawn(&self, job: &DownloadJob, run: u64, executable_guard: OwnedRwLockReadGuard<()>) -> Result<CommandChild> {
+        let executable = get_executable_path(&self.app_handle).map_err(|e| anyhow!(e))?;
+        let archive_path = get_archive_path(&self.app_handle)?;
+        let (mut rx, child) = self.app_handle.shell()
+            .sidecar(executable)?
+            .args(build_download_args(&job.request, find_ffmpeg(&self.app_handle).as_deref(), Some(&archive_path)))
+            .spawn()?;
+
+        let app_handle = self.app_handle.clone();
+        let id = job.id.clone();
+        tauri::async_runtime::spawn(async move {
+            let manager = app_handle.state::<DownloadManager>();
+            let mut last_error = None;
+
+            while let Some(event) = rx.recv().await {
+                match event {
+                    CommandEvent::Stdout(bytes) => manager.handle_output(&id, run, &decode_line(&bytes)),
+                    CommandEvent::Stderr(bytes) => {
+                        let line = decode_line(&bytes);
+                        if line.starts_with("ERROR") {
+                            last_error = Some(line.trim().to_string());
+                        }
+                    }
+                    CommandEvent::Error(message) => last_error = Some(message),
+                    CommandEvent::Terminated(payload) => {
+                        drop(executable_guard);
+                        manager.finish(&id, run, payload.code == Some(0), last_error.take());
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+        });
+
+        Ok(child)
+    }
+
+    fn handle_output(&self, id: &str, run: u64, line: &str) {
+        let mut state = self.state.lock().unwrap();
+        if state.children.get(id).map(|c| c.run) != Some(run) {
+            return;
+        }
+        let Some(job) = state.jobs.iter_mut().find(|j| j.id == id) else { return };
+
+        match parse_line(line) {
+            OutputLine::Progress(progress) => {
+                job.progress = progress;
+                let job = job.clone();
+                drop(state);
+                if let Err(e) = self.app_handle.emit("download_progress", &job) {
+                    println!("Failed to emit download progress: {}", e);
+                }
+            }
+            OutputLine::Destination(path) => {
+                if !job.files.contains(&path) {
+                    job.files.push(path);
+                }
+            }
+            OutputLine::Archived => job.skipped = true,
+            OutputLine::Other => {}
+        }
+    }
+
+    /// yt-dlpの終了時に呼ばれる
+    fn finish(&self, id: &str, run: u64, success: bool, error: Option<String>) {
+        {
+            // 一時停止・キャンセルで止めたプロセスの終了通知は無視する
+            let mut state = self.state.lock().unwrap();
+            if state.children.get(id).map(|c| c.run) != Some(run) {
+                return;
+            }
+            state.children.remove(id);
+        }
+        let outdated = !success && error.as_deref().is_some_and(is_outdated_extractor_error);
+
+        let result = self.update_job(id, |job, _| {
+            if success {
+                job.status = DownloadStatus::Completed;
+                job.progress.percent = 100.0;
+                job.progress.eta_seconds = None;
+                job.finished_at = Some(now_secs());
+            } else if job.retries < job.max_retries {
+                job.retries += 1;
+                println!("Download {} failed, retrying ({}/{})", job.id, job.retries, job.max_retries);
+                job.status = DownloadStatus::Queued;
+                job.error = error;
+            } else {
+                job.status = DownloadStatus::Failed;
+                job.error = Some(error.unwrap_or_else(|| "yt-dlp exited with an error".to_string()));
+                job.finished_at = Some(now_secs());
+            }
+            Ok(())
+        });
+
+        match result {
+            Ok(job) if job.status == DownloadStatus::Completed => {
+                // 完了したものはライブラリに登録してオフライン再生できるようにする
+                if let Err(e) = self.app_handle.state::<Library>().register_download(&job.files, job.request.from_rule) {
+                    println!("Failed to register download {} to library: {}", id, e);
+                }
+            }
+            // 再試行の前にyt-dlpの更新を試す (更新しなければそのまま再試行する)
+            Ok(job) if outdated && job.status == DownloadStatus::Queued => {
+                self.waiting_for_update.store(true, Ordering::SeqCst);
+                let app_handle = self.app_handle.clone();
+                tauri::async_runtime::spawn(async move {
+                    update_after_extractor_error(&app_handle).await;
+                    let manager = app_handle.state::<DownloadManager>();
+                    manager.waiting_for_update.store(false, Ordering::SeqCst);
+                    manager.pump();
+                });
+            }
+            Ok(_) => {}
+            Err(e) => println!("Failed to update download {}: {}", id, e),
+        }
+        self.pump();
+    }
+
+    /// プロセスを止めて .part を残し、後で再開できるようにする
+    pub fn pause(&self, id: &str) -> Result<DownloadJob> {
+        let job = self.update_job(id, |job, children| {
+            match job.status {
+                DownloadStatus::Downloading | DownloadStatus::Queued => {
+                    stop_child(children, &job.id)?;
+                    job.status = DownloadStatus::Paused;
+                    job.progress.speed = None;
+                    job.progress.eta_seconds = None;
+                    Ok(())
+                }
+                _ => Err(anyhow!("このダウンロードは一時停止できません")),
+            }
+        })?;
+        self.pump();
+        Ok(job)
+    }
+
+    pub fn resume(&self, id: &str) -> Result<DownloadJob> {
+        let job = self.update_job(id, |job, _| {
+            if job.status != DownloadStatus::Paused {
+                return Err(anyhow!("このダウンロードは一時停止されていません"));
+            }
+            job.status = DownloadStatus::Queued;
+            Ok(())
+        })?;
+        self.pump();
+        Ok(job)
+    }
+
+    /// 失敗・キャンセルしたジョブをリトライ回数をリセットして再投入する
+    pub fn retry(&self, id: &str) -> Result<DownloadJob> {
+        let job = self.update_job(id, |job, _| {
+            if !matches!(job.status, DownloadStatus::Failed | DownloadStatus::Canceled) {
+                return Err(anyhow!("このダウンロードは再試行できません"));
+            }
+            job.status = DownloadStatus::Queued;
+            job.retries = 0;
+            job.error = None;
+            job.finished_at = None;
+            Ok(())
+        })?;
+        self.pump();
+        Ok(job)
+    }
+
+    pub fn cancel(&self, id: &str) -> Result<DownloadJob> {
+        let job = self.update_job(id, |job, children| {
+            if matches!(job.status, DownloadStatus::Completed | DownloadStatus::Canceled) {
+                return Err(anyhow!("このダウンロードはキャンセルできません"));
+            }
+            stop_child(children, &job.id)?;
+            job.status = DownloadStatus::Canceled;
+            job.finished_at = Some(now_secs());
+            Ok(())
+        })?;
+
+        // プロセス終了を待たずに消すと書き込み中のファイルが残るため少し待つ
+        let files = job.files.clone();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+            remove_partial_files(&files);
+        });
+
+        self.pump();
+        Ok(job)
+    }
+
+    /// 実行中でないジョブを一覧から削除する
+    pub fn remove(&self, id: &str) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if state.children.contains_key(id) {
+            return Err(anyhow!("実行中のダウンロードは削除できません"));
+        }
+        state.jobs.retain(|j| j.id != id);
+        self.save(&state.jobs)
+    }
+}
+
+
+// --- Tauri Commands ---
+
+#[tauri::command]
+pub fn enqueue_download(
+    manager: State<'_, DownloadManager>,
+    video_url: String,
+    output_path: String,
+    format_id: Option<String>,
+    preset: Option<DownloadPreset>,
+    options: Option<DownloadOptions>,
+) -> Result<DownloadJob, String> {
+    let request = DownloadRequest::new(video_url, output_path, format_id, preset, options);
+    manager.enqueue(request).map_err(|e| e.to_string())
+}
+
+/// 選択できるプリセットとその内容を返す
+#[tauri::command]
+pub fn list_download_presets() -> Vec<(DownloadPreset, DownloadOptions)> {
+    [DownloadPreset::Best, DownloadPreset::Compatible, DownloadPreset::DataSaver, DownloadPreset::Archive, DownloadPreset::Audio]
+        .into_iter()
+        .map(|preset| (preset, preset.options()))
+        .collect()
+}
+
+#[tauri::command]
+pub fn list_downloads(manager: State<'_, DownloadManager>) -> Result<Vec<DownloadJob>, String> {
+    Ok(manager.list())
+}
+
+#[tauri::command]
+pub fn pause_download(manager: State<'_, DownloadManager>, id: String) -> Result<DownloadJob, String> {
+    manager.pause(&id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn resume_download(manager: State<'_, DownloadManager>, id: String) -> Result<DownloadJob, String> {
+    manager.resume(&id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn retry_download(manager: State<'_, DownloadManager>, id: String) -> Result<DownloadJob, String> {
+    manager.retry(&id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn cancel_download(manager: State<'_, DownloadManager>, id: String) -> Result<DownloadJob, String> {
+    manager.cancel(&id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn remove_download(manager: State<'_, DownloadManager>, id: String) -> Result<(), String> {
+    manager.remove(&id).map_err(|e| e.to_string())
+}
@@ -0,0 +1,4 @@
+pub mod archive;
+pub mod manager;
+pub mod options;
+pub mod progress;
\ No newline at end of file
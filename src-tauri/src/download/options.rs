@@ -0,0 +1,259 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+// --- 型定義 ---
+
+/// マージ後のコンテナ
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum MergeFormat {
+    Mp4,
+    #[default]
+    Mkv,
+    Webm,
+}
+
+impl MergeFormat {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Mp4 => "mp4",
+            Self::Mkv => "mkv",
+            Self::Webm => "webm",
+        }
+    }
+}
+
+/// 音声のみで保存する場合の形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioFormat {
+    #[default]
+    Opus,
+    M4a,
+    Mp3,
+}
+
+impl AudioFormat {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Opus => "opus",
+            Self::M4a => "m4a",
+            Self::Mp3 => "mp3",
+        }
+    }
+
+    /// 再エンコードを避けるため、同じコーデックの音声を優先して選ぶ
+    fn format_selector(&self) -> &'static str {
+        match self {
+            Self::Opus => "ba[acodec=opus]/ba/b",
+            Self::M4a => "ba[ext=m4a]/ba/b",
+            Self::Mp3 => "ba/b",
+        }
+    }
+}
+
+/// 音声のみの抽出オプション
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct AudioOptions {
+    pub format: AudioFormat,
+    /// kbps (未指定ならyt-dlpの既定の品質)
+    pub bitrate: Option<u32>,
+    /// 指定が無い場合はアーティスト → アップローダーの順で使う
+    pub artist: Option<String>,
+    /// 指定が無い場合はアルバム → プレイリスト名 → チャンネル名の順で使う
+    pub album: Option<String>,
+    /// チャプターごとに別ファイルに分割する
+    pub split_chapters: bool,
+}
+
+/// ダウンロード後の処理を含むオプション
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DownloadOptions {
+    /// 高さの上限 (例: 1080)
+    pub max_height: Option<u32>,
+    /// H.264 + AAC を優先する (再生環境を選ばない)
+    pub prefer_compatible: bool,
+    pub merge_format: MergeFormat,
+    pub embed_metadata: bool,
+    pub embed_thumbnail: bool,
+    pub embed_chapters: bool,
+    /// 埋め込む字幕の言語 (例: ["ja", "en"])
+    pub subtitle_langs: Vec<String>,
+    /// 自動生成字幕も対象にする
+    pub auto_subtitles: bool,
+    /// 指定した場合は音声のみを抽出する
+    pub audio: Option<AudioOptions>,
+}
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        DownloadPreset::Best.options()
+    }
+}
+
+/// フロントエンドから選べるダウンロードのプリセット
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DownloadPreset {
+    /// 最高画質の映像+音声をマージし、メタデータ等を埋め込む
+    Best,
+    /// どのプレイヤーでも再生できる mp4 (H.264 + AAC)
+    Compatible,
+    /// 容量を抑えた 720p
+    DataSaver,
+    /// 字幕を含めて全て埋め込む保存用
+    Archive,
+    /// 音声のみ (opus) でカバー画像とタグを付ける
+    Audio,
+}
+
+impl DownloadPreset {
+    pub fn options(&self) -> DownloadOptions {
+        let base = DownloadOptions {
+            max_height: None,
+            prefer_compatible: false,
+            merge_format: MergeFormat::Mkv,
+            embed_metadata: true,
+            embed_thumbnail: true,
+            embed_chapters: true,
+            subtitle_langs: Vec::new(),
+            auto_subtitles: false,
+            audio: None,
+        };
+
+        match self {
+            Self::Best => base,
+            Self::Compatible => DownloadOptions {
+                prefer_compatible: true,
+                merge_format: MergeFormat::Mp4,
+                ..base
+            },
+            Self::DataSaver => DownloadOptions {
+                max_height: Some(720),
+                prefer_compatible: true,
+                merge_format: MergeFormat::Mp4,
+                embed_thumbnail: false,
+                ..base
+            },
+            Self::Archive => DownloadOptions {
+                subtitle_langs: vec!["all".to_string(), "-live_chat".to_string()],
+                ..base
+            },
+            Self::Audio => DownloadOptions {
+                audio: Some(AudioOptions::default()),
+                ..base
+            },
+        }
+    }
+}
+
+
+// --- プライベートヘルパー関数 ---
+
+/// 固定の文字列を --parse-metadata のFROMとして渡せる形にする
+/// (英字だけの文字列はフィールド名と解釈されるため、空のフィールドを付けてテンプレート扱いにする)
+fn literal_template(text: &str) -> String {
+    format!("{}%(tag_literal|)s", text.replace('%', "%%").replace(':', "\\:"))
+}
+
+fn audio_args(audio: &AudioOptions, embed_thumbnail: bool, output_path: &str) -> Vec<String> {
+    let mut args = vec![
+        "--extract-audio".to_string(),
+        "--audio-format".to_string(),
+        audio.format.as_str().to_string(),
+    ];
+    if let Some(bitrate) = audio.bitrate {
+        args.push("--audio-quality".to_string());
+        args.push(format!("{}K", bitrate));
+    }
+
+    // タグは --embed-metadata で書き込まれる meta_ フィールドに入れる
+    let artist = audio.artist.as_deref()
+        .map(literal_template)
+        .unwrap_or_else(|| "%(artist,artists,creator,uploader)s".to_string());
+    let album = audio.album.as_deref()
+        .map(literal_template)
+        .unwrap_or_else(|| "%(album,playlist_title,channel)s".to_string());
+    args.extend([
+        "--embed-metadata".to_string(),
+        "--parse-metadata".to_string(),
+        format!("{}:%(meta_artist)s", artist),
+        "--parse-metadata".to_string(),
+        format!("{}:%(meta_album)s", album),
+    ]);
+
+    if embed_thumbnail {
+        // mp3/m4a のカバー画像はjpgである必要がある
+        args.extend([
+            "--embed-thumbnail".to_string(),
+            "--convert-thumbnails".to_string(),
+            "jpg".to_string(),
+        ]);
+    }
+    if audio.split_chapters {
+        // 分割したファイルは本体と同じフォルダに置く
+        let dir = Path::new(output_path).parent().unwrap_or(Path::new(""));
+        let chapter_template = dir.join("%(title)s - %(section_number)03d %(section_title)s.%(ext)s");
+        args.extend([
+            "--split-chapters".to_string(),
+            "--output".to_string(),
+            format!("chapter:{}", chapter_template.to_string_lossy()),
+        ]);
+    }
+
+    args
+}
+
+
+// --- 公開関数 ---
+
+impl DownloadOptions {
+    /// 映像と音声を別々に選んでマージするフォーマット指定
+    pub fn format_selector(&self) -> String {
+        if let Some(audio) = &self.audio {
+            return audio.format.format_selector().to_string();
+        }
+
+        let height = self.max_height.map(|h| format!("[height<={}]", h)).unwrap_or_default();
+        if self.prefer_compatible {
+            format!("bv*{h}[vcodec^=avc1]+ba[ext=m4a]/b{h}[ext=mp4]/bv*{h}+ba/b{h}/b", h = height)
+        } else {
+            format!("bv*{h}+ba/b{h}/b", h = height)
+        }
+    }
+
+    /// マージ・埋め込み・字幕の引数
+    pub fn postprocess_args(&self, output_path: &str) -> Vec<String> {
+        if let Some(audio) = &self.audio {
+            return audio_args(audio, self.embed_thumbnail, output_path);
+        }
+
+        let mut args = vec![
+            "--merge-output-format".to_string(),
+            self.merge_format.as_str().to_string(),
+        ];
+
+        if self.embed_metadata {
+            args.push("--embed-metadata".to_string());
+        }
+        if self.embed_thumbnail {
+            args.push("--embed-thumbnail".to_string());
+        }
+        if self.embed_chapters {
+            args.push("--embed-chapters".to_string());
+        }
+        if !self.subtitle_langs.is_empty() {
+            args.push("--write-subs".to_string());
+            if self.auto_subtitles {
+                args.push("--write-auto-subs".to_string());
+            }
+            args.push("--sub-langs".to_string());
+            args.push(self.subtitle_langs.join(","));
+            args.push("--embed-subs".to_string());
+        }
+
+        args
+    }
+}
@@ -0,0 +1,119 @@
+use serde::{Deserialize, Serialize};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+// `--newline` 指定時のyt-dlpの進捗行
+// [download]  45.3% of ~ 120.50MiB at    2.31MiB/s ETA 00:29 (frag 12/40)
+// [download] 100% of   10.00MiB in 00:00:03 at 3.02MiB/s
+static PROGRESS_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(concat!(
+        r"^\[download\]\s+(?P<percent>[\d.]+)%",
+        r"\s+of\s+(?P<estimate>~)?\s*(?P<total>\S+)",
+        r"(?:\s+in\s+\S+)?",
+        r"(?:\s+at\s+(?P<speed>Unknown B/s|\S+))?",
+        r"(?:\s+ETA\s+(?P<eta>\S+))?",
+        r"(?:\s+\(frag\s+(?P<frag>\d+)/(?P<frags>\d+)\))?",
+    )).unwrap()
+});
+
+// [download] / [ExtractAudio] / [SplitChapters] などの書き出し先
+static DESTINATION_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\[\w+\] (?:.*; )?Destination: (?P<path>.+)$").unwrap()
+});
+
+static MERGER_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"^\[Merger\] Merging formats into "(?P<path>.+)"$"#).unwrap()
+});
+
+static INFO_JSON_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\[info\] Writing video metadata as JSON to: (?P<path>.+)$").unwrap()
+});
+
+static ARCHIVED_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\[download\] .+ has already been recorded in the archive").unwrap()
+});
+
+static ALREADY_DOWNLOADED_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\[download\] (?P<path>.+) has already been downloaded").unwrap()
+});
+
+
+/// ダウンロード1件分の進捗
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DownloadProgress {
+    pub percent: f64,
+    pub total_bytes: Option<u64>,
+    /// 合計サイズが推定値 (`~`) かどうか
+    pub total_is_estimate: bool,
+    /// バイト/秒
+    pub speed: Option<f64>,
+    pub eta_seconds: Option<u64>,
+    pub fragment_index: Option<u32>,
+    pub fragment_count: Option<u32>,
+}
+
+/// yt-dlpの出力1行を解釈した結果
+#[derive(Debug)]
+pub enum OutputLine {
+    Progress(DownloadProgress),
+    /// 書き出し先ファイル (マージ後のファイルを含む)
+    Destination(String),
+    /// ダウンロードアーカイブに記録済みのためスキップされた
+    Archived,
+    Other,
+}
+
+
+/// "120.50MiB" のようなサイズ表記をバイト数に変換する
+fn parse_size(text: &str) -> Option<f64> {
+    let split = text.find(|c: char| !(c.is_ascii_digit() || c == '.'))?;
+    let (number, unit) = text.split_at(split);
+    let value: f64 = number.parse().ok()?;
+    let multiplier = match unit {
+        "B" => 1.0,
+        "KiB" => 1024.0,
+        "MiB" => 1024.0 * 1024.0,
+        "GiB" => 1024.0 * 1024.0 * 1024.0,
+        "TiB" => 1024.0 * 1024.0 * 1024.0 * 1024.0,
+        "KB" | "kB" => 1000.0,
+        "MB" => 1000.0 * 1000.0,
+        "GB" => 1000.0 * 1000.0 * 1000.0,
+        _ => return None,
+    };
+    Some(value * multiplier)
+}
+
+/// "1:02:03" や "00:29" を秒に変換する
+fn parse_eta(text: &str) -> Option<u64> {
+    text.split(':').try_fold(0u64, |acc, part| part.parse::<u64>().ok().map(|v| acc * 60 + v))
+}
+
+pub fn parse_line(line: &str) -> OutputLine {
+    let line = line.trim();
+
+    if let Some(caps) = PROGRESS_RE.captures(line) {
+        return OutputLine::Progress(DownloadProgress {
+            percent: caps["percent"].parse().unwrap_or(0.0),
+            total_bytes: parse_size(&caps["total"]).map(|b| b as u64),
+            total_is_estimate: caps.name("estimate").is_some(),
+            speed: caps.name("speed")
+                .and_then(|m| m.as_str().strip_suffix("/s"))
+                .and_then(parse_size),
+            eta_seconds: caps.name("eta").and_then(|m| parse_eta(m.as_str())),
+            fragment_index: caps.name("frag").and_then(|m| m.as_str().parse().ok()),
+            fragment_count: caps.name("frags").and_then(|m| m.as_str().parse().ok()),
+        });
+    }
+
+    if ARCHIVED_RE.is_match(line) {
+        return OutputLine::Archived;
+    }
+
+    for re in [&*DESTINATION_RE, &*MERGER_RE, &*INFO_JSON_RE, &*ALREADY_DOWNLOADED_RE] {
+        if let Some(caps) = re.captures(line) {
+            return OutputLine::Destination(caps["path"].to_string());
+        }
+    }
+
+    OutputLine::Other
+}
@@ -1,19 +1,83 @@
 
 use serde::{ Serialize};
-use tauri::{ AppHandle};
-use tauri_plugin_shell::ShellExt;
+use tauri::{ AppHandle, Manager, State};
 
 
 mod dlp; // Import the module for channel information
+mod thumbnail;
+mod download;
+mod library;
+mod subscription;
+mod db;
+mod backup;
 
 use dlp::get_channel::dlp_get_channel_info;
 use dlp::get_channel::dlp_get_channel_newvideo;
 use dlp::get_channel::dlp_get_video_info;
 use dlp::get_channel::dlp_get_channel_morevideo;
 use dlp::get_channel::dlp_get_stream_url;
-use dlp::get_channel::get_executable_path;
-
-use dlp::dlp_manager::check_and_update;
+use dlp::runner::run_ytdlp;
+use dlp::resolve_stream::dlp_resolve_streams;
+
+use dlp::dlp_manager::{check_and_update, get_ytdlp_updater_settings, set_ytdlp_updater_settings, get_ytdlp_versions, rollback_ytdlp, install_ytdlp_from_file, check_for_update, get_last_ytdlp_update_check, YtDlpManager};
+
+use thumbnail::cache::ThumbnailCache;
+use thumbnail::cache::get_cached_thumbnail;
+use thumbnail::cache::prefetch_thumbnails;
+use thumbnail::cache::get_thumbnail_cache_stats;
+use thumbnail::cache::set_thumbnail_cache_budget;
+use thumbnail::cache::clear_thumbnail_cache;
+
+use download::manager::{DownloadManager, DownloadRequest};
+use download::options::{AudioOptions, DownloadOptions, DownloadPreset};
+use download::manager::enqueue_download;
+use download::manager::list_downloads;
+use download::manager::list_download_presets;
+use download::manager::pause_download;
+use download::manager::resume_download;
+use download::manager::retry_download;
+use download::manager::cancel_download;
+use download::manager::remove_download;
+use download::archive::get_download_archive;
+use download::archive::import_download_archive;
+use download::archive::prune_download_archive;
+
+use library::index::Library;
+use library::index::list_library;
+use library::index::scan_library;
+use library::index::get_library_dir;
+use library::index::set_library_dir;
+use library::index::get_offline_source;
+use library::index::remove_library_entry;
+
+use subscription::rules::DownloadRules;
+use subscription::rules::get_download_rules;
+use subscription::rules::set_download_rule;
+use subscription::rules::remove_download_rule;
+use subscription::rules::preview_download_rule;
+use subscription::rules::apply_download_rules;
+
+use subscription::scheduler::SubscriptionScheduler;
+use subscription::scheduler::get_refresh_settings;
+use subscription::scheduler::set_refresh_settings;
+use subscription::scheduler::refresh_subscriptions;
+use subscription::scheduler::get_new_videos;
+use subscription::scheduler::clear_new_videos;
+use subscription::feed::get_subscription_feed;
+use subscription::opml::export_subscriptions_opml;
+use subscription::opml::import_subscriptions_opml;
+use subscription::takeout::import_takeout_subscriptions;
+use subscription::takeout::import_takeout_history;
+use subscription::freetube::import_freetube_db;
+use subscription::newpipe::import_newpipe;
+
+use db::connection::Database;
+use db::subscriptions::{list_subscriptions, add_subscription, remove_subscription, set_subscription_cache, move_subscription};
+use db::playlists::{list_playlists, save_playlist, remove_playlist, add_playlist_item, remove_playlist_item};
+use db::history::{list_history, add_history, remove_history, clear_history};
+use backup::archive::{export_backup, import_backup};
+use db::search::{search_local, rebuild_search_index};
+use db::playback::{update_playback_position, get_resume_position, mark_watched, get_watch_progress, get_continue_watching};
 
 
 
@@ -46,18 +110,14 @@ async fn get_video_info(
     app_handle: AppHandle,
     video_url: String,
 ) -> Result<VideoInfo, String> {
-    let shell = app_handle.shell();
-
     // yt-dlpで動画情報をJSON形式で取得
-    let output = shell
-        .sidecar(get_executable_path(&app_handle).map_err(|e| format!("Failed to get executable path: {}", e))?)
-        .unwrap()
-        .arg("--cookies-from-browser")
-        .arg("firefox")
-        .args(["--dump-json", "--no-download", &video_url])
-        .output()
-        .await
-        .map_err(|e| format!("Failed to execute yt-dlp: {}", e))?;
+    let output = run_ytdlp(&app_handle, &[
+        "--cookies-from-browser",
+        "firefox",
+        "--dump-json",
+        "--no-download",
+        &video_url,
+    ]).await?;
 
     if !output.status.success() {
         return Err(format!(
@@ -110,45 +170,40 @@ async fn get_video_info(
 
 
 
+/// ダウンロードキューに追加し、ジョブIDを返す
+/// 進捗は `download_progress` / `download_status` イベントで通知される
 #[tauri::command]
-async fn download_video(
-    app_handle: AppHandle,
+fn download_video(
+    manager: State<'_, DownloadManager>,
     video_url: String,
     output_path: String,
     format_id: Option<String>,
+    preset: Option<DownloadPreset>,
 ) -> Result<String, String> {
-    let shell = app_handle.shell();
-
-    let mut args = vec!["--output", &output_path];
-
-    if let Some(ref format) = format_id {
-        args.push("--format");
-        args.push(format);
-    } else {
-        args.push("--format");
-        args.push("best[ext=mp4]/best");
-    }
-
-    args.push(&video_url);
-
-    let output = shell
-        .sidecar(get_executable_path(&app_handle).map_err(|e| format!("Failed to get executable path: {}", e))?)
-        .unwrap()
-        .arg("--cookies-from-browser")
-        .arg("firefox")
-        .args(args)
-        .output()
-        .await
-        .map_err(|e| format!("Failed to execute yt-dlp: {}", e))?;
+    let job = manager
+        .enqueue(DownloadRequest::new(video_url, output_path, format_id, preset, None))
+        .map_err(|e| e.to_string())?;
 
-    if !output.status.success() {
-        return Err(format!(
-            "yt-dlp error: {}",
-            String::from_utf8_lossy(&output.stderr)
-        ));
-    }
+    Ok(job.id)
+}
 
-    Ok("Download completed successfully".to_string())
+/// 音声のみを抽出してダウンロードキューに追加し、ジョブIDを返す
+#[tauri::command]
+fn download_audio(
+    manager: State<'_, DownloadManager>,
+    video_url: String,
+    output_path: String,
+    audio: AudioOptions,
+) -> Result<String, String> {
+    let options = DownloadOptions {
+        audio: Some(audio),
+        ..DownloadPreset::Audio.options()
+    };
+    let job = manager
+        .enqueue(DownloadRequest::new(video_url, output_path, None, None, Some(options)))
+        .map_err(|e| e.to_string())?;
+
+    Ok(job.id)
 }
 
 
@@ -161,15 +216,115 @@ pub fn run() {
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_store::Builder::default().build())
+        .plugin(tauri_plugin_notification::init())
+        .setup(|app| {
+            let database = Database::new(app.handle())?;
+            app.manage(database);
+
+            let ytdlp_manager = YtDlpManager::new(app.handle())?;
+            app.manage(ytdlp_manager);
+
+            let thumbnail_cache = ThumbnailCache::new(app.handle())?;
+            app.manage(thumbnail_cache);
+
+            let library = Library::new(app.handle())?;
+            app.manage(library);
+
+            let download_rules = DownloadRules::new(app.handle())?;
+            app.manage(download_rules);
+
+            let download_manager = DownloadManager::new(app.handle())?;
+            app.manage(download_manager);
+            // 前回終了時に残っていたジョブを再開する
+            app.state::<DownloadManager>().pump();
+
+            let scheduler = SubscriptionScheduler::new(app.handle())?;
+            app.manage(scheduler);
+            SubscriptionScheduler::start(app.handle());
+            // 更新時にダウンロードを再開するので、DownloadManager の登録後に始める
+            YtDlpManager::start(app.handle());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             get_video_info,
             download_video,
+            download_audio,
             dlp_get_channel_info,
             dlp_get_channel_newvideo,
             dlp_get_video_info,
             dlp_get_channel_morevideo,
             dlp_get_stream_url,
+            dlp_resolve_streams,
             check_and_update,
+            get_ytdlp_updater_settings,
+            set_ytdlp_updater_settings,
+            get_ytdlp_versions,
+            rollback_ytdlp,
+            install_ytdlp_from_file,
+            check_for_update,
+            get_last_ytdlp_update_check,
+            get_cached_thumbnail,
+            prefetch_thumbnails,
+            get_thumbnail_cache_stats,
+            set_thumbnail_cache_budget,
+            clear_thumbnail_cache,
+            enqueue_download,
+            list_downloads,
+            list_download_presets,
+            pause_download,
+            resume_download,
+            retry_download,
+            cancel_download,
+            remove_download,
+            get_download_archive,
+            import_download_archive,
+            prune_download_archive,
+            list_library,
+            scan_library,
+            get_library_dir,
+            set_library_dir,
+            get_offline_source,
+            remove_library_entry,
+            get_download_rules,
+            set_download_rule,
+            remove_download_rule,
+            preview_download_rule,
+            apply_download_rules,
+            get_refresh_settings,
+            set_refresh_settings,
+            refresh_subscriptions,
+            get_new_videos,
+            clear_new_videos,
+            get_subscription_feed,
+            export_subscriptions_opml,
+            import_subscriptions_opml,
+            import_takeout_subscriptions,
+            import_takeout_history,
+            import_freetube_db,
+            import_newpipe,
+            list_subscriptions,
+            add_subscription,
+            remove_subscription,
+            set_subscription_cache,
+            move_subscription,
+            list_playlists,
+            save_playlist,
+            remove_playlist,
+            add_playlist_item,
+            remove_playlist_item,
+            list_history,
+            add_history,
+            remove_history,
+            clear_history,
+            update_playback_position,
+            get_resume_position,
+            mark_watched,
+            get_watch_progress,
+            get_continue_watching,
+            search_local,
+            rebuild_search_index,
+            export_backup,
+            import_backup,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,386 @@
+use tauri::{AppHandle, Manager, State};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use anyhow::{Result, Context, anyhow};
+
+use crate::db::search::{record_video_metadata, VideoMetadata};
+
+// --- 定数定義 ---
+const INDEX_FILE_NAME: &str = "library.json";
+const LIBRARY_DIR_NAME: &str = "yt-tur";
+const INFO_JSON_SUFFIX: &str = ".info.json";
+const MEDIA_EXTENSIONS: [&str; 8] = ["mp4", "mkv", "webm", "m4a", "opus", "mp3", "ogg", "flac"];
+
+
+// --- 型定義 ---
+
+/// ダウンロード済みの動画1件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryEntry {
+    pub video_id: String,
+    pub title: String,
+    pub channel: Option<String>,
+    pub channel_id: Option<String>,
+    /// 再生するメディアファイル
+    pub path: String,
+    pub info_json_path: Option<String>,
+    pub format_id: Option<String>,
+    pub ext: String,
+    pub size_bytes: u64,
+    pub duration: Option<f64>,
+    pub thumbnail: Option<String>,
+    /// アプリでダウンロードした動画は完了した時刻、フォルダから見つけたものはファイルの更新日時
+    pub downloaded_at: u64,
+    /// 自動ダウンロードのルールでダウンロードしたもの
+    #[serde(default)]
+    pub from_rule: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct LibraryIndex {
+    library_dir: Option<String>,
+    entries: Vec<LibraryEntry>,
+}
+
+/// ダウンロード済み動画の索引
+pub struct Library {
+    app_handle: AppHandle,
+    index_path: PathBuf,
+    default_dir: PathBuf,
+    index: Mutex<LibraryIndex>,
+}
+
+/// オフライン再生用の情報
+#[derive(Debug, Serialize)]
+pub struct OfflineSource {
+    pub video_id: String,
+    pub path: String,
+    pub ext: String,
+}
+
+
+// --- プライベートヘルパー関数 ---
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// watch?v= / youtu.be / shorts のURL、または動画IDそのものからIDを取り出す
+pub fn extract_video_id(video_url: &str) -> Option<String> {
+    let Ok(parsed) = url::Url::parse(video_url) else {
+        let is_id = video_url.len() == 11 && video_url.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        return is_id.then(|| video_url.to_string());
+    };
+
+    if let Some((_, id)) = parsed.query_pairs().find(|(key, _)| key == "v") {
+        return Some(id.to_string());
+    }
+    let segments: Vec<&str> = parsed.path_segments()?.filter(|s| !s.is_empty()).collect();
+    match (parsed.host_str()?, segments.as_slice()) {
+        (host, [id]) if host.ends_with("youtu.be") => Some(id.to_string()),
+        (_, ["shorts" | "live" | "embed", id, ..]) => Some(id.to_string()),
+        _ => None,
+    }
+}
+
+/// `.info.json` と同じ名前のメディアファイルを探す
+fn find_media_file(info_json_path: &Path, info: &Value) -> Option<PathBuf> {
+    let file_name = info_json_path.file_name()?.to_string_lossy().to_string();
+    let stem = file_name.strip_suffix(INFO_JSON_SUFFIX)?;
+    let dir = info_json_path.parent()?;
+
+    // yt-dlpが最終的に書き出したパスが残っていればそれを優先する
+    if let Some(filepath) = info["filepath"].as_str().or(info["_filename"].as_str()) {
+        let path = PathBuf::from(filepath);
+        if path.exists() {
+            return Some(path);
+        }
+    }
+
+    let preferred = info["ext"].as_str().into_iter();
+    preferred.chain(MEDIA_EXTENSIONS)
+        .map(|ext| dir.join(format!("{}.{}", stem, ext)))
+        .find(|path| path.exists())
+}
+
+fn entry_from_info(media_path: &Path, info_json_path: Option<&Path>, info: &Value) -> Option<LibraryEntry> {
+    let video_id = info["id"].as_str()?.to_string();
+    let metadata = std::fs::metadata(media_path).ok()?;
+    let ext = media_path.extension().map(|e| e.to_string_lossy().to_string()).unwrap_or_default();
+
+    Some(LibraryEntry {
+        video_id,
+        title: info["title"].as_str().unwrap_or("Unknown").to_string(),
+        channel: info["channel"].as_str().or(info["uploader"].as_str()).map(str::to_string),
+        channel_id: info["channel_id"].as_str().map(str::to_string),
+        path: media_path.to_string_lossy().to_string(),
+        info_json_path: info_json_path.map(|p| p.to_string_lossy().to_string()),
+        format_id: info["format_id"].as_str().map(str::to_string),
+        ext,
+        size_bytes: metadata.len(),
+        duration: info["duration"].as_f64(),
+        thumbnail: info["thumbnail"].as_str().map(str::to_string),
+        downloaded_at: metadata.modified().ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs())
+            .unwrap_or_else(now_secs),
+        from_rule: false,
+    })
+}
+
+/// フォルダ以下の `.info.json` を再帰的に集める
+fn collect_info_jsons(dir: &Path, found: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            collect_info_jsons(&path, found)?;
+        } else if entry.file_name().to_string_lossy().ends_with(INFO_JSON_SUFFIX) {
+            found.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn read_info_json(path: &Path) -> Result<Value> {
+    let content = std::fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+
+impl Library {
+    pub fn new(app_handle: &AppHandle) -> Result<Self> {
+        let app_data_dir = app_handle.path().app_data_dir().context("アプリデータディレクトリの取得に失敗")?;
+        std::fs::create_dir_all(&app_data_dir)?;
+        let index_path = app_data_dir.join(INDEX_FILE_NAME);
+
+        let default_dir = app_handle.path().video_dir()
+            .unwrap_or_else(|_| app_data_dir.clone())
+            .join(LIBRARY_DIR_NAME);
+
+        let index: LibraryIndex = match std::fs::read_to_string(&index_path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_default(),
+            Err(_) => LibraryIndex::default(),
+        };
+
+        let library = Self {
+            app_handle: app_handle.clone(),
+            index_path,
+            default_dir,
+            index: Mutex::new(index),
+        };
+
+        // フォルダごとではなく、索引にあるメディアファイルだけ再生を許可する
+        // (スコープは後から狭められないので、フォルダを変えても前のフォルダが開いたままにならないようにする)
+        for entry in library.list() {
+            library.allow_asset(Path::new(&entry.path))?;
+        }
+
+        Ok(library)
+    }
+
+    fn allow_asset(&self, path: &Path) -> Result<()> {
+        self.app_handle.asset_protocol_scope()
+            .allow_file(path)
+            .context("asset protocolのスコープ設定に失敗")
+    }
+
+    /// ライブラリフォルダにできるか確かめる。ルートやホームフォルダ (とその上位) は受け付けない
+    fn validate_library_dir(&self, dir: &str) -> Result<()> {
+        let path = std::fs::canonicalize(dir)
+            .with_context(|| format!("フォルダが見つかりません: {}", dir))?;
+        if !path.is_dir() {
+            return Err(anyhow!("フォルダではありません: {}", dir));
+        }
+        if path.parent().is_none() {
+            return Err(anyhow!("ドライブのルートはライブラリフォルダにできません: {}", dir));
+        }
+        if let Ok(home_dir) = self.app_handle.path().home_dir() {
+            let home_dir = std::fs::canonicalize(&home_dir).unwrap_or(home_dir);
+            if home_dir.starts_with(&path) {
+                return Err(anyhow!("ホームフォルダはライブラリフォルダにできません: {}", dir));
+            }
+        }
+        Ok(())
+    }
+
+    fn save(&self, index: &LibraryIndex) -> Result<()> {
+        let temp_path = self.index_path.with_extension("json.tmp");
+        std::fs::write(&temp_path, serde_json::to_string_pretty(index)?)?;
+        std::fs::rename(&temp_path, &self.index_path)?;
+        Ok(())
+    }
+
+    pub fn library_dir(&self) -> PathBuf {
+        self.index.lock().unwrap().library_dir.as_ref()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| self.default_dir.clone())
+    }
+
+    pub fn set_library_dir(&self, dir: &str) -> Result<()> {
+        self.validate_library_dir(dir)?;
+
+        let mut index = self.index.lock().unwrap();
+        index.library_dir = Some(dir.to_string());
+        self.save(&index)
+    }
+
+    pub fn list(&self) -> Vec<LibraryEntry> {
+        self.index.lock().unwrap().entries.clone()
+    }
+
+    /// 再生可能なファイルが残っているエントリを返す
+    pub fn find(&self, video_id: &str) -> Option<LibraryEntry> {
+        self.index.lock().unwrap().entries.iter()
+            .find(|e| e.video_id == video_id && Path::new(&e.path).exists())
+            .cloned()
+    }
+
+    fn upsert(&self, entry: LibraryEntry) -> Result<()> {
+        self.allow_asset(Path::new(&entry.path))?;
+        let mut index = self.index.lock().unwrap();
+        index.entries.retain(|e| e.video_id != entry.video_id);
+        index.entries.push(entry);
+        self.save(&index)
+    }
+
+    /// ダウンロード完了時に、書き出されたファイルから登録する
+    pub fn register_download(&self, files: &[String], from_rule: bool) -> Result<Option<LibraryEntry>> {
+        let Some(info_json_path) = files.iter().map(Path::new).find(|p| p.to_string_lossy().ends_with(INFO_JSON_SUFFIX)) else {
+            return Ok(None);
+        };
+        let info = read_info_json(info_json_path)?;
+
+        // 最後に書き出されたメディアファイル (マージ・音声抽出後のファイル) を使う
+        let media_path = files.iter().rev()
+            .map(PathBuf::from)
+            .find(|p| {
+                p.exists() && p.extension()
+                    .map(|e| MEDIA_EXTENSIONS.contains(&e.to_string_lossy().as_ref()))
+                    .unwrap_or(false)
+            })
+            .or_else(|| find_media_file(info_json_path, &info))
+            .ok_or_else(|| anyhow!("ダウンロードしたファイルが見つかりません"))?;
+
+        let mut entry = entry_from_info(&media_path, Some(info_json_path), &info)
+            .ok_or_else(|| anyhow!("動画情報の読み込みに失敗しました"))?;
+        // yt-dlpはファイルの更新日時を投稿日にするので、ダウンロードした時刻は完了時に記録する
+        entry.downloaded_at = now_secs();
+        entry.from_rule = from_rule;
+        if let Some(metadata) = VideoMetadata::from_info_json(&info) {
+            record_video_metadata(&self.app_handle, &[metadata]);
+        }
+        self.upsert(entry.clone())?;
+        Ok(Some(entry))
+    }
+
+    /// ライブラリフォルダの `.info.json` から索引を作り直す
+    pub fn scan(&self) -> Result<Vec<LibraryEntry>> {
+        let dir = self.library_dir();
+        let mut info_jsons = Vec::new();
+        if dir.exists() {
+            collect_info_jsons(&dir, &mut info_jsons)?;
+        }
+
+        let mut entries: Vec<LibraryEntry> = Vec::new();
+        let mut metadata = Vec::new();
+        for info_json_path in info_jsons {
+            let Ok(info) = read_info_json(&info_json_path) else {
+                println!("Failed to read {:?}", info_json_path);
+                continue;
+            };
+            let Some(media_path) = find_media_file(&info_json_path, &info) else { continue };
+            metadata.extend(VideoMetadata::from_info_json(&info));
+            if let Some(entry) = entry_from_info(&media_path, Some(&info_json_path), &info) {
+                entries.retain(|e| e.video_id != entry.video_id);
+                entries.push(entry);
+            }
+        }
+
+        record_video_metadata(&self.app_handle, &metadata);
+        for entry in &entries {
+            self.allow_asset(Path::new(&entry.path))?;
+        }
+
+        let mut index = self.index.lock().unwrap();
+        // 登録済みのものは、記録したダウンロード時刻とルールの印を引き継ぐ
+        for entry in entries.iter_mut() {
+            if let Some(known) = index.entries.iter().find(|e| e.video_id == entry.video_id && e.path == entry.path) {
+                entry.downloaded_at = known.downloaded_at;
+                entry.from_rule = known.from_rule;
+            }
+        }
+        // フォルダ外に保存されたもので、ファイルが残っているものは維持する
+        let outside: Vec<LibraryEntry> = index.entries.iter()
+            .filter(|e| !Path::new(&e.path).starts_with(&dir) && Path::new(&e.path).exists())
+            .filter(|e| !entries.iter().any(|n| n.video_id == e.video_id))
+            .cloned()
+            .collect();
+        entries.extend(outside);
+
+        index.entries = entries.clone();
+        self.save(&index)?;
+        Ok(entries)
+    }
+
+    pub fn remove(&self, video_id: &str, delete_files: bool) -> Result<()> {
+        let mut index = self.index.lock().unwrap();
+        if delete_files {
+            for entry in index.entries.iter().filter(|e| e.video_id == video_id) {
+                // 既に消されていても索引からは外す
+                match std::fs::remove_file(&entry.path) {
+                    Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                    _ => {}
+                }
+                if let Some(info_json_path) = &entry.info_json_path {
+                    let _ = std::fs::remove_file(info_json_path);
+                }
+            }
+        }
+        index.entries.retain(|e| e.video_id != video_id);
+        self.save(&index)
+    }
+}
+
+
+// --- Tauri Commands ---
+
+#[tauri::command]
+pub fn list_library(library: State<'_, Library>) -> Result<Vec<LibraryEntry>, String> {
+    Ok(library.list())
+}
+
+#[tauri::command]
+pub async fn scan_library(library: State<'_, Library>) -> Result<Vec<LibraryEntry>, String> {
+    library.scan().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_library_dir(library: State<'_, Library>) -> Result<String, String> {
+    Ok(library.library_dir().to_string_lossy().to_string())
+}
+
+#[tauri::command]
+pub fn set_library_dir(library: State<'_, Library>, dir: String) -> Result<(), String> {
+    library.set_library_dir(&dir).map_err(|e| e.to_string())
+}
+
+/// オフラインで再生できる場合はローカルファイルのパスを返す
+/// (フロントエンドでは convertFileSrc でasset URLに変換する)
+#[tauri::command]
+pub fn get_offline_source(library: State<'_, Library>, video_url: String) -> Result<Option<OfflineSource>, String> {
+    let Some(video_id) = extract_video_id(&video_url) else { return Ok(None) };
+    Ok(library.find(&video_id).map(|entry| OfflineSource {
+        video_id: entry.video_id,
+        path: entry.path,
+        ext: entry.ext,
+    }))
+}
+
+#[tauri::command]
+pub fn remove_library_entry(library: State<'_, Library>, video_id: String, delete_files: bool) -> Result<(), String> {
+    library.remove(&video_id, delete_files).map_err(|e| e.to_string())
+}
@@ -0,0 +1,234 @@
+use tauri::{AppHandle, Emitter, Manager};
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use futures::stream::{self, StreamExt};
+
+use crate::db::connection::Database;
+use crate::db::history::get_watched_video_ids;
+use crate::db::playback::WatchProgress;
+use crate::db::search::{record_video_metadata, VideoMetadata};
+use crate::db::subscriptions::{get_followed_channels, FollowedChannel};
+use crate::subscription::fetch::fetch_channel_videos;
+use crate::subscription::video::ChannelVideo;
+
+// --- 定数定義 ---
+const FETCH_CONCURRENCY: usize = 4;
+const DEFAULT_PAGE_SIZE: usize = 30;
+const DEFAULT_VIDEOS_PER_CHANNEL: u32 = 30;
+/// この時間内ならページ送りで取得し直さない
+const FEED_CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+/// ショートとみなす長さ (秒)
+const SHORTS_MAX_DURATION: u64 = 60;
+
+// ページ送りのために、最後に取得したフィード全体を保持する
+// (フィルタをかける前のものを持ち、フィルタはページを作るたびに今の視聴履歴でかける)
+static FEED_CACHE: Mutex<Option<CachedFeed>> = Mutex::new(None);
+
+
+// --- 型定義 ---
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct FeedQuery {
+    /// 0始まり
+    pub page: usize,
+    pub page_size: Option<usize>,
+    pub videos_per_channel: Option<u32>,
+    pub hide_shorts: bool,
+    pub hide_watched: bool,
+    /// 配信中・配信予定を隠す
+    pub hide_live: bool,
+    /// キャッシュを使わずに取得し直す
+    pub force_refresh: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FeedPage {
+    pub videos: Vec<ChannelVideo>,
+    pub page: usize,
+    pub page_size: usize,
+    /// フィルタ後の件数
+    pub total: usize,
+    pub has_more: bool,
+    /// 取得に失敗したチャンネルID
+    pub failed_channels: Vec<String>,
+    /// このページの動画の視聴状況 (動画IDがキー、記録の無い動画は含まない)
+    pub progress: HashMap<String, WatchProgress>,
+}
+
+/// チャンネル1件の取得が終わるたびに送るイベント
+#[derive(Debug, Clone, Serialize)]
+struct FeedPartial {
+    channel_id: String,
+    videos: Vec<ChannelVideo>,
+    error: Option<String>,
+    completed: usize,
+    total: usize,
+}
+
+struct CachedFeed {
+    fetched_at: Instant,
+    /// 取得したときのフォロー中のチャンネル (並べ替え済み)。フォローが変わったら取得し直す
+    channel_ids: Vec<String>,
+    videos_per_channel: u32,
+    videos: Vec<ChannelVideo>,
+    failed_channels: Vec<String>,
+}
+
+
+// --- プライベートヘルパー関数 ---
+
+fn is_short(video: &ChannelVideo) -> bool {
+    video.url.contains("/shorts/") || video.duration.is_some_and(|d| d <= SHORTS_MAX_DURATION)
+}
+
+fn is_live(video: &ChannelVideo) -> bool {
+    matches!(video.live_status.as_deref(), Some("is_live") | Some("is_upcoming"))
+}
+
+struct FeedFilter {
+    hide_shorts: bool,
+    hide_live: bool,
+    watched: Option<HashSet<String>>,
+}
+
+impl FeedFilter {
+    fn new(app_handle: &AppHandle, query: &FeedQuery) -> Self {
+        let watched = query.hide_watched.then(|| {
+            get_watched_video_ids(app_handle).unwrap_or_else(|e| {
+                println!("Failed to read watch history: {}", e);
+                HashSet::new()
+            })
+        });
+        Self { hide_shorts: query.hide_shorts, hide_live: query.hide_live, watched }
+    }
+
+    fn keep(&self, video: &ChannelVideo) -> bool {
+        if self.hide_shorts && is_short(video) {
+            return false;
+        }
+        if self.hide_live && is_live(video) {
+            return false;
+        }
+        if self.watched.as_ref().is_some_and(|w| w.contains(&video.video_id)) {
+            return false;
+        }
+        true
+    }
+}
+
+/// フォロー中の全チャンネルを並列に取得し、新しい順に並べる
+async fn fetch_feed(app_handle: &AppHandle, channels: Vec<FollowedChannel>, videos_per_channel: u32, filter: &FeedFilter) -> Result<CachedFeed, String> {
+    let channel_ids = sorted_channel_ids(&channels);
+    let total = channels.len();
+
+    let mut results = stream::iter(channels)
+        .map(|channel| async move {
+            let result = fetch_channel_videos(app_handle, &channel.id, videos_per_channel).await;
+            (channel, result)
+        })
+        .buffer_unordered(FETCH_CONCURRENCY);
+
+    let mut videos = Vec::new();
+    let mut failed_channels = Vec::new();
+    let mut completed = 0;
+    while let Some((channel, result)) = results.next().await {
+        completed += 1;
+        let partial = match result {
+            Ok(channel_videos) => {
+                let partial = FeedPartial {
+                    channel_id: channel.id.clone(),
+                    videos: channel_videos.iter().filter(|v| filter.keep(v)).cloned().collect(),
+                    error: None,
+                    completed,
+                    total,
+                };
+                let metadata: Vec<VideoMetadata> = channel_videos.iter().map(VideoMetadata::from).collect();
+                record_video_metadata(app_handle, &metadata);
+                videos.extend(channel_videos);
+                partial
+            }
+            Err(e) => {
+                println!("Failed to fetch feed for {}: {}", channel.id, e);
+                failed_channels.push(channel.id.clone());
+                FeedPartial { channel_id: channel.id, videos: Vec::new(), error: Some(e), completed, total }
+            }
+        };
+        if let Err(e) = app_handle.emit("subscription_feed_partial", &partial) {
+            println!("Failed to emit feed progress: {}", e);
+        }
+    }
+
+    // 投稿日時が分からない動画は末尾に回す
+    videos.sort_by_key(|v| std::cmp::Reverse(v.timestamp.unwrap_or(0)));
+
+    Ok(CachedFeed { fetched_at: Instant::now(), channel_ids, videos_per_channel, videos, failed_channels })
+}
+
+fn sorted_channel_ids(channels: &[FollowedChannel]) -> Vec<String> {
+    let mut ids: Vec<String> = channels.iter().map(|c| c.id.clone()).collect();
+    ids.sort();
+    ids
+}
+
+fn build_page(feed: &CachedFeed, query: &FeedQuery, filter: &FeedFilter) -> FeedPage {
+    let page_size = query.page_size.unwrap_or(DEFAULT_PAGE_SIZE).max(1);
+    let filtered: Vec<&ChannelVideo> = feed.videos.iter().filter(|v| filter.keep(v)).collect();
+    let start = (query.page * page_size).min(filtered.len());
+    let end = (start + page_size).min(filtered.len());
+
+    FeedPage {
+        videos: filtered[start..end].iter().map(|v| (*v).clone()).collect(),
+        page: query.page,
+        page_size,
+        total: filtered.len(),
+        has_more: end < filtered.len(),
+        failed_channels: feed.failed_channels.clone(),
+        progress: HashMap::new(),
+    }
+}
+
+/// キャッシュが新しければそこから、古ければ取得し直してページを作る
+async fn build_feed_page(app_handle: &AppHandle, query: Option<FeedQuery>) -> Result<FeedPage, String> {
+    let query = query.unwrap_or_default();
+    let videos_per_channel = query.videos_per_channel.unwrap_or(DEFAULT_VIDEOS_PER_CHANNEL).max(1);
+    let filter = FeedFilter::new(app_handle, &query);
+    let channels = get_followed_channels(app_handle).map_err(|e| e.to_string())?;
+
+    if !query.force_refresh {
+        let cache = FEED_CACHE.lock().unwrap();
+        if let Some(feed) = cache.as_ref() {
+            let fresh = feed.fetched_at.elapsed() < FEED_CACHE_TTL
+                && feed.videos_per_channel >= videos_per_channel
+                && feed.channel_ids == sorted_channel_ids(&channels);
+            if fresh {
+                return Ok(build_page(feed, &query, &filter));
+            }
+        }
+    }
+
+    let feed = fetch_feed(app_handle, channels, videos_per_channel, &filter).await?;
+    let page = build_page(&feed, &query, &filter);
+    *FEED_CACHE.lock().unwrap() = Some(feed);
+    Ok(page)
+}
+
+
+// --- Tauri Commands ---
+
+/// フォロー中のチャンネルの動画をまとめたフィードを返す
+///
+/// チャンネルごとの取得結果は `subscription_feed_partial` イベントで先に送られる。
+/// 2ページ目以降は直前に取得したフィードから切り出す。
+#[tauri::command]
+pub async fn get_subscription_feed(app_handle: AppHandle, query: Option<FeedQuery>) -> Result<FeedPage, String> {
+    let mut page = build_feed_page(&app_handle, query).await?;
+    let video_ids: Vec<String> = page.videos.iter().map(|v| v.video_id.clone()).collect();
+    page.progress = app_handle.state::<Database>().watch_progress(&video_ids).unwrap_or_else(|e| {
+        println!("Failed to read watch progress: {}", e);
+        HashMap::new()
+    });
+    Ok(page)
+}
@@ -0,0 +1,130 @@
+use serde_json::Value;
+
+use crate::dlp::runner::run_ytdlp;
+use crate::subscription::video::ChannelVideo;
+
+
+// --- プライベートヘルパー関数 ---
+
+/// グレゴリオ暦の日付を1970-01-01からの日数に変換する
+pub(crate) fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y.rem_euclid(400);
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+/// "20240131" 形式の日付をUNIX秒に変換する
+fn upload_date_to_timestamp(date: &str) -> Option<u64> {
+    if date.len() != 8 {
+        return None;
+    }
+    let year: i64 = date[0..4].parse().ok()?;
+    let month: i64 = date[4..6].parse().ok()?;
+    let day: i64 = date[6..8].parse().ok()?;
+
+    u64::try_from(days_from_civil(year, month, day) * 86400).ok()
+}
+
+fn entry_to_video(entry: &Value, channel_id: &str, channel_name: Option<&str>) -> Option<ChannelVideo> {
+    let video_id = entry["id"].as_str()?.to_string();
+    let url = entry["url"].as_str()
+        .filter(|u| u.starts_with("http"))
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("https://www.youtube.com/watch?v={}", video_id));
+
+    let thumbnail = entry["thumbnail"].as_str()
+        .or_else(|| entry["thumbnails"].as_array()?.last()?["url"].as_str())
+        .map(str::to_string);
+
+    Some(ChannelVideo {
+        title: entry["title"].as_str().unwrap_or("Unknown").to_string(),
+        channel_id: entry["channel_id"].as_str().unwrap_or(channel_id).to_string(),
+        channel_name: entry["channel"].as_str().or(channel_name).map(str::to_string),
+        duration: entry["duration"].as_f64().map(|d| d as u64),
+        timestamp: entry["timestamp"].as_u64()
+            .or_else(|| entry["release_timestamp"].as_u64())
+            .or_else(|| entry["upload_date"].as_str().and_then(upload_date_to_timestamp)),
+        thumbnail,
+        view_count: entry["view_count"].as_u64(),
+        live_status: entry["live_status"].as_str().map(str::to_string),
+        video_id,
+        url,
+    })
+}
+
+
+// --- 公開関数 ---
+
+/// `-J` の出力 (チャンネルのプレイリスト) から動画一覧を取り出す
+pub fn parse_channel_playlist(json_value: &Value, channel_id: &str) -> Vec<ChannelVideo> {
+    let channel_name = json_value["channel"].as_str().or(json_value["uploader"].as_str());
+    json_value["entries"].as_array()
+        .map(|entries| entries.iter().filter_map(|e| entry_to_video(e, channel_id, channel_name)).collect())
+        .unwrap_or_default()
+}
+
+/// チャンネルの新しい順の動画を `limit` 件取得する
+///
+/// `--flat-playlist` で一覧ページだけを取得するため、動画ごとのページは開かない。
+/// 投稿日時は "3日前" のような表記からの概算になる。
+pub async fn fetch_channel_videos(app_handle: &tauri::AppHandle, channel_id: &str, limit: u32) -> Result<Vec<ChannelVideo>, String> {
+    let channel_url = format!("https://www.youtube.com/channel/{}/videos", channel_id);
+
+    let output = run_ytdlp(app_handle, &[
+        "--no-warnings",
+        "--cookies-from-browser",
+        "firefox",
+        "--flat-playlist",
+        "--extractor-args",
+        "youtubetab:approximate_date",
+        "--playlist-items",
+        &format!("1-{}", limit),
+        "-J",
+        &channel_url,
+    ]).await?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "yt-dlp error: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    let json_value: Value = serde_json::from_slice(&output.stdout)
+        .map_err(|e| format!("Failed to parse JSON: {}", e))?;
+
+    Ok(parse_channel_playlist(&json_value, channel_id))
+}
+
+/// チャンネルのURL (@ハンドル、/c/、/user/ など) から正規のチャンネルIDと名前を取得する
+pub async fn resolve_channel(app_handle: &tauri::AppHandle, channel_url: &str) -> Result<(String, Option<String>), String> {
+    let output = run_ytdlp(app_handle, &[
+        "--no-warnings",
+        "--flat-playlist",
+        "--playlist-items",
+        "0",
+        "-J",
+        channel_url,
+    ]).await?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "yt-dlp error: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    let json_value: Value = serde_json::from_slice(&output.stdout)
+        .map_err(|e| format!("Failed to parse JSON: {}", e))?;
+
+    let channel_id = json_value["channel_id"].as_str()
+        .or(json_value["uploader_id"].as_str().filter(|id| id.starts_with("UC")))
+        .ok_or_else(|| "Channel id not found".to_string())?;
+    let channel_name = json_value["channel"].as_str().or(json_value["uploader"].as_str());
+
+    Ok((channel_id.to_string(), channel_name.map(str::to_string)))
+}
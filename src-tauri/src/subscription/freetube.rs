@@ -0,0 +1,104 @@
+use tauri::AppHandle;
+use std::collections::{HashMap, HashSet};
+use serde_json::Value;
+
+use crate::db::history::HistoryEntry;
+use crate::db::playlists::{ImportedPlaylist, PlaylistVideo};
+use crate::subscription::import::{import_data, DataImportReport, ImportedData, SubscriptionEntry};
+
+
+// --- プライベートヘルパー関数 ---
+
+/// NeDBのファイル (1行1ドキュメント) を読み込む
+///
+/// 更新は同じ `_id` の行が後ろに追記され、削除は `$$deleted` の行で表されるので、最後の状態だけを残す。
+fn read_nedb(content: &str) -> Vec<Value> {
+    let mut documents: Vec<Option<Value>> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+
+    for line in content.lines().filter(|l| !l.trim().is_empty()) {
+        let Ok(document) = serde_json::from_str::<Value>(line) else { continue };
+        let Some(id) = document["_id"].as_str().map(str::to_string) else { continue };
+        let deleted = document["$$deleted"].as_bool().unwrap_or(false);
+        let value = (!deleted).then_some(document);
+
+        match positions.get(&id) {
+            Some(&index) => documents[index] = value,
+            None => {
+                positions.insert(id, documents.len());
+                documents.push(value);
+            }
+        }
+    }
+
+    documents.into_iter().flatten().collect()
+}
+
+fn watch_url(video_id: &str) -> String {
+    format!("https://www.youtube.com/watch?v={}", video_id)
+}
+
+fn to_playlist_video(video: &Value) -> Option<(String, PlaylistVideo)> {
+    let video_id = video["videoId"].as_str()?.to_string();
+    Some((video_id.clone(), PlaylistVideo {
+        title: video["title"].as_str().unwrap_or("").to_string(),
+        url: watch_url(&video_id),
+        id: video["authorId"].as_str().unwrap_or("").to_string(),
+        channel_name: video["author"].as_str().unwrap_or("").to_string(),
+    }))
+}
+
+
+// --- 公開関数 ---
+
+/// FreeTubeの `profiles.db`・`history.db`・`playlists.db` を読み込む
+///
+/// どのファイルかは各行の内容で判断するので、どれを渡してもよい。
+pub fn parse_freetube_db(content: &str) -> ImportedData {
+    let mut data = ImportedData::default();
+    let mut seen_channels = HashSet::new();
+
+    for document in read_nedb(content) {
+        if let Some(subscriptions) = document["subscriptions"].as_array() {
+            // プロファイルごとに同じチャンネルが入っている
+            for channel in subscriptions {
+                let Some(id) = channel["id"].as_str() else { continue };
+                if !seen_channels.insert(id.to_string()) {
+                    continue;
+                }
+                data.subscriptions.push(SubscriptionEntry {
+                    title: channel["name"].as_str().unwrap_or("").to_string(),
+                    xml_url: None,
+                    html_url: Some(format!("https://www.youtube.com/channel/{}", id)),
+                });
+            }
+        } else if let Some(videos) = document["videos"].as_array().filter(|_| document["playlistName"].is_string()) {
+            let id = document["_id"].as_str().unwrap_or_default();
+            data.playlists.push(ImportedPlaylist {
+                id: format!("freetube-{}", id),
+                name: document["playlistName"].as_str().unwrap_or("FreeTube").to_string(),
+                videos: videos.iter().filter_map(to_playlist_video).collect(),
+            });
+        } else if let Some(video_id) = document["videoId"].as_str().filter(|_| document["timeWatched"].is_number()) {
+            data.history.push(HistoryEntry {
+                title: document["title"].as_str().unwrap_or("").to_string(),
+                url: watch_url(video_id),
+                id: document["authorId"].as_str().unwrap_or("").to_string(),
+                channel_name: document["author"].as_str().unwrap_or("").to_string(),
+                timestamp: document["timeWatched"].as_f64().map(|t| t as u64).unwrap_or(0),
+            });
+        }
+    }
+
+    data
+}
+
+
+// --- Tauri Commands ---
+
+/// FreeTubeの `.db` ファイルからフォロー・履歴・プレイリストを取り込む
+#[tauri::command]
+pub async fn import_freetube_db(app_handle: AppHandle, source_path: String) -> Result<DataImportReport, String> {
+    let content = std::fs::read_to_string(&source_path).map_err(|e| e.to_string())?;
+    import_data(&app_handle, parse_freetube_db(&content)).await
+}
@@ -0,0 +1,190 @@
+use tauri::{AppHandle, Emitter};
+use std::collections::HashSet;
+use serde::Serialize;
+use futures::stream::{self, StreamExt};
+
+use crate::db::history::{merge_history, HistoryEntry, HistoryImportReport};
+use crate::db::playlists::{merge_playlists, ImportedPlaylist, PlaylistImportReport};
+use crate::db::subscriptions::{add_followed_channels, get_followed_channels, FollowedChannel};
+use crate::subscription::fetch::resolve_channel;
+
+// --- 定数定義 ---
+const RESOLVE_CONCURRENCY: usize = 4;
+
+
+// --- 型定義 ---
+
+/// 取り込むチャンネル1件 (OPMLの `<outline>`、Takeoutの行など)
+///
+/// `xml_url`・`html_url` のどちらかからチャンネルIDを解決する。
+#[derive(Debug, Clone, Serialize)]
+pub struct SubscriptionEntry {
+    pub title: String,
+    pub xml_url: Option<String>,
+    pub html_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportStatus {
+    Added,
+    /// 既にフォロー済み、またはファイル内で重複
+    Skipped,
+    Failed,
+}
+
+/// 1件処理するたびに送るイベント
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportProgress {
+    pub completed: usize,
+    pub total: usize,
+    pub title: String,
+    pub channel_id: Option<String>,
+    pub status: ImportStatus,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportFailure {
+    pub title: String,
+    pub url: Option<String>,
+    pub error: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct SubscriptionImportReport {
+    pub added: Vec<FollowedChannel>,
+    pub skipped: Vec<FollowedChannel>,
+    pub failed: Vec<ImportFailure>,
+}
+
+/// 他のアプリのデータをまとめて取り込んだ結果 (ファイルに含まれない種類は None)
+#[derive(Debug, Default, Serialize)]
+pub struct DataImportReport {
+    pub subscriptions: Option<SubscriptionImportReport>,
+    pub history: Option<HistoryImportReport>,
+    pub playlists: Option<PlaylistImportReport>,
+}
+
+/// 他のアプリのエクスポートから読み取ったデータ
+#[derive(Debug, Default)]
+pub struct ImportedData {
+    pub subscriptions: Vec<SubscriptionEntry>,
+    pub history: Vec<HistoryEntry>,
+    pub playlists: Vec<ImportedPlaylist>,
+}
+
+
+// --- プライベートヘルパー関数 ---
+
+/// URLからチャンネルIDを直接取り出す (取り出せなければ None)
+pub(crate) fn channel_id_from_url(value: &str) -> Option<String> {
+    let parsed = url::Url::parse(value).ok()?;
+    if let Some((_, id)) = parsed.query_pairs().find(|(key, _)| key == "channel_id") {
+        return Some(id.to_string());
+    }
+    let mut segments = parsed.path_segments()?;
+    while let Some(segment) = segments.next() {
+        if segment == "channel" {
+            return segments.next().filter(|id| id.starts_with("UC")).map(str::to_string);
+        }
+    }
+    None
+}
+
+/// yt-dlpで解決するためのチャンネルページのURL
+fn channel_page_url(entry: &SubscriptionEntry) -> Option<String> {
+    if let Some(html_url) = entry.html_url.as_deref().filter(|u| u.contains("youtube.com/")) {
+        return Some(html_url.to_string());
+    }
+    // 旧形式のフィード (?user=名前)
+    let parsed = url::Url::parse(entry.xml_url.as_deref()?).ok()?;
+    parsed.query_pairs()
+        .find(|(key, _)| key == "user")
+        .map(|(_, user)| format!("https://www.youtube.com/user/{}", user))
+}
+
+async fn resolve_entry(app_handle: &AppHandle, entry: &SubscriptionEntry) -> Result<FollowedChannel, String> {
+    let direct_id = [&entry.xml_url, &entry.html_url].into_iter()
+        .flatten()
+        .find_map(|u| channel_id_from_url(u));
+    if let Some(id) = direct_id {
+        return Ok(FollowedChannel { id, channel_name: entry.title.clone(), cache: None });
+    }
+
+    let page_url = channel_page_url(entry).ok_or_else(|| "YouTubeのチャンネルではありません".to_string())?;
+    let (id, name) = resolve_channel(app_handle, &page_url).await?;
+    let channel_name = if entry.title.is_empty() { name.unwrap_or_default() } else { entry.title.clone() };
+    Ok(FollowedChannel { id, channel_name, cache: None })
+}
+
+
+// --- 公開関数 ---
+
+/// 各エントリをチャンネルIDに解決してフォローに追加し、進捗を `subscription_import_progress` で送る
+pub async fn import_entries(app_handle: &AppHandle, entries: Vec<SubscriptionEntry>) -> Result<SubscriptionImportReport, String> {
+    let mut known: HashSet<String> = get_followed_channels(app_handle)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|c| c.id)
+        .collect();
+
+    let total = entries.len();
+    let mut results = stream::iter(entries)
+        .map(|entry| async move {
+            let result = resolve_entry(app_handle, &entry).await;
+            (entry, result)
+        })
+        .buffered(RESOLVE_CONCURRENCY);
+
+    let mut report = SubscriptionImportReport::default();
+    let mut completed = 0;
+    while let Some((entry, result)) = results.next().await {
+        completed += 1;
+        let progress = match result {
+            Ok(channel) => {
+                let status = if known.insert(channel.id.clone()) {
+                    report.added.push(channel.clone());
+                    ImportStatus::Added
+                } else {
+                    report.skipped.push(channel.clone());
+                    ImportStatus::Skipped
+                };
+                ImportProgress { completed, total, title: channel.channel_name, channel_id: Some(channel.id), status, error: None }
+            }
+            Err(error) => {
+                println!("Failed to resolve subscription {}: {}", entry.title, error);
+                report.failed.push(ImportFailure {
+                    title: entry.title.clone(),
+                    url: entry.html_url.or(entry.xml_url),
+                    error: error.clone(),
+                });
+                ImportProgress { completed, total, title: entry.title, channel_id: None, status: ImportStatus::Failed, error: Some(error) }
+            }
+        };
+        if let Err(e) = app_handle.emit("subscription_import_progress", &progress) {
+            println!("Failed to emit import progress: {}", e);
+        }
+    }
+
+    add_followed_channels(app_handle, &report.added).map_err(|e| e.to_string())?;
+    if !report.added.is_empty() {
+        app_handle.emit("follow_channels_changed", ()).map_err(|e| e.to_string())?;
+    }
+    Ok(report)
+}
+
+/// フォロー・履歴・プレイリストをまとめて取り込む
+pub async fn import_data(app_handle: &AppHandle, data: ImportedData) -> Result<DataImportReport, String> {
+    let mut report = DataImportReport::default();
+    if !data.subscriptions.is_empty() {
+        report.subscriptions = Some(import_entries(app_handle, data.subscriptions).await?);
+    }
+    if !data.history.is_empty() {
+        report.history = Some(merge_history(app_handle, data.history).map_err(|e| e.to_string())?);
+    }
+    if !data.playlists.is_empty() {
+        report.playlists = Some(merge_playlists(app_handle, data.playlists).map_err(|e| e.to_string())?);
+    }
+    Ok(report)
+}
@@ -0,0 +1,11 @@
+pub mod feed;
+pub mod fetch;
+pub mod freetube;
+pub mod import;
+pub mod newpipe;
+pub mod opml;
+pub mod rss;
+pub mod rules;
+pub mod scheduler;
+pub mod takeout;
+pub mod video;
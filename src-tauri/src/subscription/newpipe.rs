@@ -0,0 +1,183 @@
+use tauri::AppHandle;
+use std::io::Read;
+use std::path::Path;
+use serde_json::Value;
+use rusqlite::{Connection, OpenFlags};
+use anyhow::{Result, Context, anyhow};
+
+use crate::db::history::HistoryEntry;
+use crate::db::playlists::{ImportedPlaylist, PlaylistVideo};
+use crate::library::index::extract_video_id;
+use crate::subscription::import::{channel_id_from_url, import_data, DataImportReport, ImportedData, SubscriptionEntry};
+
+// --- 定数定義 ---
+/// NewPipeのサービスIDのうちYouTube
+const YOUTUBE_SERVICE_ID: i64 = 0;
+const DATABASE_ENTRY_NAME: &str = "newpipe.db";
+
+
+// --- プライベートヘルパー関数 ---
+
+/// `streams` テーブルの1行
+struct StreamRow {
+    url: String,
+    title: String,
+    uploader: String,
+    uploader_url: Option<String>,
+}
+
+impl StreamRow {
+    fn channel_id(&self) -> String {
+        self.uploader_url.as_deref().and_then(channel_id_from_url).unwrap_or_default()
+    }
+
+    fn to_playlist_video(&self) -> Option<(String, PlaylistVideo)> {
+        let video_id = extract_video_id(&self.url)?;
+        Some((video_id, PlaylistVideo {
+            title: self.title.clone(),
+            url: self.url.clone(),
+            id: self.channel_id(),
+            channel_name: self.uploader.clone(),
+        }))
+    }
+}
+
+fn read_subscriptions(conn: &Connection) -> Result<Vec<SubscriptionEntry>> {
+    let mut statement = conn.prepare("SELECT url, name FROM subscriptions WHERE service_id = ?1")?;
+    let rows = statement.query_map([YOUTUBE_SERVICE_ID], |row| {
+        Ok(SubscriptionEntry {
+            html_url: Some(row.get(0)?),
+            title: row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+            xml_url: None,
+        })
+    })?;
+    Ok(rows.collect::<rusqlite::Result<_>>()?)
+}
+
+fn read_history(conn: &Connection) -> Result<Vec<HistoryEntry>> {
+    let mut statement = conn.prepare(
+        "SELECT s.url, s.title, s.uploader, s.uploader_url, h.access_date
+         FROM stream_history h JOIN streams s ON s.uid = h.stream_id
+         WHERE s.service_id = ?1",
+    )?;
+    let rows = statement.query_map([YOUTUBE_SERVICE_ID], |row| {
+        let stream = StreamRow {
+            url: row.get(0)?,
+            title: row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+            uploader: row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+            uploader_url: row.get(3)?,
+        };
+        let access_date: i64 = row.get(4)?;
+        Ok(HistoryEntry {
+            id: stream.channel_id(),
+            title: stream.title,
+            url: stream.url,
+            channel_name: stream.uploader,
+            timestamp: u64::try_from(access_date).unwrap_or(0),
+        })
+    })?;
+    Ok(rows.collect::<rusqlite::Result<_>>()?)
+}
+
+/// ローカルのプレイリストだけを読む (リモートのプレイリストはURLの参照しか無い)
+fn read_playlists(conn: &Connection) -> Result<Vec<ImportedPlaylist>> {
+    let mut playlists_statement = conn.prepare("SELECT uid, name FROM playlists")?;
+    let playlists: Vec<(i64, String)> = playlists_statement
+        .query_map([], |row| Ok((row.get(0)?, row.get::<_, Option<String>>(1)?.unwrap_or_default())))?
+        .collect::<rusqlite::Result<_>>()?;
+
+    let mut items_statement = conn.prepare(
+        "SELECT s.url, s.title, s.uploader, s.uploader_url
+         FROM playlist_stream_join j JOIN streams s ON s.uid = j.stream_id
+         WHERE j.playlist_id = ?1 AND s.service_id = ?2
+         ORDER BY j.join_index",
+    )?;
+
+    let mut result = Vec::new();
+    for (uid, name) in playlists {
+        let streams: Vec<StreamRow> = items_statement
+            .query_map([uid, YOUTUBE_SERVICE_ID], |row| {
+                Ok(StreamRow {
+                    url: row.get(0)?,
+                    title: row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+                    uploader: row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+                    uploader_url: row.get(3)?,
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+
+        result.push(ImportedPlaylist {
+            id: format!("newpipe-{}", uid),
+            name,
+            videos: streams.iter().filter_map(StreamRow::to_playlist_video).collect(),
+        });
+    }
+    Ok(result)
+}
+
+
+// --- 公開関数 ---
+
+/// NewPipeの購読のエクスポート (`{"subscriptions": [...]}`) を読み込む
+pub fn parse_newpipe_json(content: &str) -> Result<ImportedData> {
+    let json_value: Value = serde_json::from_str(content).context("JSONの解析に失敗")?;
+    let subscriptions = json_value["subscriptions"].as_array()
+        .ok_or_else(|| anyhow!("NewPipeの購読ファイルではありません"))?
+        .iter()
+        .filter(|s| s["service_id"].as_i64() == Some(YOUTUBE_SERVICE_ID))
+        .filter_map(|s| Some(SubscriptionEntry {
+            title: s["name"].as_str().unwrap_or("").to_string(),
+            xml_url: None,
+            html_url: Some(s["url"].as_str()?.to_string()),
+        }))
+        .collect();
+
+    Ok(ImportedData { subscriptions, ..Default::default() })
+}
+
+/// NewPipeのデータベースのエクスポート (zip内の `newpipe.db`) を読み込む
+pub fn read_newpipe_zip(zip_path: &Path) -> Result<ImportedData> {
+    let file = std::fs::File::open(zip_path)?;
+    let mut archive = zip::ZipArchive::new(file).context("zipファイルを開けません")?;
+    let mut entry = archive.by_name(DATABASE_ENTRY_NAME)
+        .map_err(|_| anyhow!("zipに {} が含まれていません", DATABASE_ENTRY_NAME))?;
+
+    // SQLiteはファイルから開く必要があるので一時ファイルに展開する
+    let temp_path = std::env::temp_dir().join(format!("yt-tur-newpipe-{}.db", uuid::Uuid::new_v4()));
+    let mut bytes = Vec::new();
+    entry.read_to_end(&mut bytes)?;
+    std::fs::write(&temp_path, bytes)?;
+
+    let result = (|| -> Result<ImportedData> {
+        let conn = Connection::open_with_flags(&temp_path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        Ok(ImportedData {
+            subscriptions: read_subscriptions(&conn)?,
+            history: read_history(&conn)?,
+            playlists: read_playlists(&conn)?,
+        })
+    })();
+
+    let _ = std::fs::remove_file(&temp_path);
+    result
+}
+
+
+// --- Tauri Commands ---
+
+/// NewPipeの購読のJSON、またはデータベースのzipを取り込む
+#[tauri::command]
+pub async fn import_newpipe(app_handle: AppHandle, source_path: String) -> Result<DataImportReport, String> {
+    let path = std::path::PathBuf::from(&source_path);
+    let is_zip = path.extension().is_some_and(|e| e.eq_ignore_ascii_case("zip"));
+
+    let data = if is_zip {
+        tauri::async_runtime::spawn_blocking(move || read_newpipe_zip(&path))
+            .await
+            .map_err(|e| e.to_string())?
+    } else {
+        std::fs::read_to_string(&path).map_err(anyhow::Error::from).and_then(|c| parse_newpipe_json(&c))
+    }
+    .map_err(|e| e.to_string())?;
+
+    import_data(&app_handle, data).await
+}
@@ -0,0 +1,75 @@
+use tauri::AppHandle;
+use std::path::Path;
+use roxmltree::Document;
+
+use crate::db::subscriptions::{get_followed_channels, FollowedChannel};
+use crate::subscription::import::{import_entries, SubscriptionEntry, SubscriptionImportReport};
+use crate::subscription::rss::DEFAULT_FEED_BASE_URL;
+
+
+// --- プライベートヘルパー関数 ---
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+
+// --- 公開関数 ---
+
+pub fn parse_opml(content: &str) -> Result<Vec<SubscriptionEntry>, String> {
+    let document = Document::parse(content).map_err(|e| format!("Failed to parse OPML: {}", e))?;
+    if document.root_element().tag_name().name() != "opml" {
+        return Err("Not an OPML file".to_string());
+    }
+
+    // フォルダ分けされたOPMLもあるので、URLを持つ outline を全て拾う
+    let entries = document.descendants()
+        .filter(|n| n.tag_name().name() == "outline")
+        .filter(|n| n.has_attribute("xmlUrl") || n.has_attribute("htmlUrl"))
+        .map(|n| SubscriptionEntry {
+            title: n.attribute("title").or(n.attribute("text")).unwrap_or("").to_string(),
+            xml_url: n.attribute("xmlUrl").map(str::to_string),
+            html_url: n.attribute("htmlUrl").map(str::to_string),
+        })
+        .collect();
+    Ok(entries)
+}
+
+pub fn build_opml(channels: &[FollowedChannel]) -> String {
+    let mut opml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<opml version=\"1.1\">\n");
+    opml.push_str("  <head>\n    <title>yt-tur subscriptions</title>\n  </head>\n");
+    opml.push_str("  <body>\n    <outline text=\"YouTube Subscriptions\" title=\"YouTube Subscriptions\">\n");
+    for channel in channels {
+        let name = escape_xml(&channel.channel_name);
+        opml.push_str(&format!(
+            "      <outline text=\"{name}\" title=\"{name}\" type=\"rss\" xmlUrl=\"{}?channel_id={id}\" htmlUrl=\"https://www.youtube.com/channel/{id}\"/>\n",
+            DEFAULT_FEED_BASE_URL,
+            id = escape_xml(&channel.id),
+        ));
+    }
+    opml.push_str("    </outline>\n  </body>\n</opml>\n");
+    opml
+}
+
+
+// --- Tauri Commands ---
+
+/// フォロー中のチャンネルをOPMLとして書き出す。書き出した件数を返す
+#[tauri::command]
+pub fn export_subscriptions_opml(app_handle: AppHandle, output_path: String) -> Result<usize, String> {
+    let channels = get_followed_channels(&app_handle).map_err(|e| e.to_string())?;
+    std::fs::write(Path::new(&output_path), build_opml(&channels)).map_err(|e| e.to_string())?;
+    Ok(channels.len())
+}
+
+/// 他のクライアントやRSSリーダーのOPMLからフォローを取り込む
+#[tauri::command]
+pub async fn import_subscriptions_opml(app_handle: AppHandle, source_path: String) -> Result<SubscriptionImportReport, String> {
+    let content = std::fs::read_to_string(&source_path).map_err(|e| e.to_string())?;
+    let entries = parse_opml(&content)?;
+    import_entries(&app_handle, entries).await
+}
@@ -0,0 +1,248 @@
+use std::time::Duration;
+use once_cell::sync::Lazy;
+use roxmltree::{Document, Node};
+
+use crate::subscription::fetch::days_from_civil;
+use crate::subscription::video::ChannelVideo;
+
+// --- 定数定義 ---
+pub const DEFAULT_FEED_BASE_URL: &str = "https://www.youtube.com/feeds/videos.xml";
+const ATOM_NS: &str = "http://www.w3.org/2005/Atom";
+const YT_NS: &str = "http://www.youtube.com/xml/schemas/2015";
+const MEDIA_NS: &str = "http://search.yahoo.com/mrss/";
+const FEED_TIMEOUT: Duration = Duration::from_secs(15);
+
+static CLIENT: Lazy<reqwest::Client> = Lazy::new(|| {
+    reqwest::Client::builder()
+        .timeout(FEED_TIMEOUT)
+        .build()
+        .expect("HTTPクライアントの作成に失敗")
+});
+
+
+// --- プライベートヘルパー関数 ---
+
+fn child<'a, 'input>(node: Node<'a, 'input>, ns: &str, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|n| n.tag_name().namespace() == Some(ns) && n.tag_name().name() == name)
+}
+
+fn child_text(node: Node, ns: &str, name: &str) -> Option<String> {
+    child(node, ns, name).and_then(|n| n.text()).map(|t| t.trim().to_string())
+}
+
+/// "2024-01-31T12:34:56+00:00" 形式の日時をUNIX秒に変換する
+pub(crate) fn parse_rfc3339(value: &str) -> Option<u64> {
+    let value = value.trim();
+    if value.len() < 19 {
+        return None;
+    }
+    let year: i64 = value.get(0..4)?.parse().ok()?;
+    let month: i64 = value.get(5..7)?.parse().ok()?;
+    let day: i64 = value.get(8..10)?.parse().ok()?;
+    let hour: i64 = value.get(11..13)?.parse().ok()?;
+    let minute: i64 = value.get(14..16)?.parse().ok()?;
+    let second: i64 = value.get(17..19)?.parse().ok()?;
+
+    // 小数秒は読み飛ばし、タイムゾーンのオフセットを反映する
+    let rest = value[19..].trim_start_matches(|c: char| c == '.' || c.is_ascii_digit());
+    let offset = match rest.chars().next() {
+        Some(sign @ ('+' | '-')) => {
+            let hours: i64 = rest.get(1..3)?.parse().ok()?;
+            let minutes: i64 = rest.get(4..6)?.parse().ok()?;
+            let offset = hours * 3600 + minutes * 60;
+            if sign == '+' { offset } else { -offset }
+        }
+        _ => 0,
+    };
+
+    let seconds = days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60 + second - offset;
+    u64::try_from(seconds).ok()
+}
+
+fn entry_to_video(entry: Node, channel_id: &str, channel_name: Option<&str>) -> Option<ChannelVideo> {
+    let video_id = child_text(entry, YT_NS, "videoId")?;
+    let url = entry.children()
+        .find(|n| n.tag_name().name() == "link" && n.attribute("rel") == Some("alternate"))
+        .and_then(|n| n.attribute("href"))
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("https://www.youtube.com/watch?v={}", video_id));
+
+    let media = child(entry, MEDIA_NS, "group");
+    let thumbnail = media
+        .and_then(|m| child(m, MEDIA_NS, "thumbnail"))
+        .and_then(|n| n.attribute("url"))
+        .map(str::to_string);
+    let view_count = media
+        .and_then(|m| child(m, MEDIA_NS, "community"))
+        .and_then(|c| child(c, MEDIA_NS, "statistics"))
+        .and_then(|n| n.attribute("views"))
+        .and_then(|v| v.parse().ok());
+    let author = child(entry, ATOM_NS, "author").and_then(|a| child_text(a, ATOM_NS, "name"));
+
+    Some(ChannelVideo {
+        title: child_text(entry, ATOM_NS, "title").unwrap_or_else(|| "Unknown".to_string()),
+        channel_id: child_text(entry, YT_NS, "channelId").unwrap_or_else(|| channel_id.to_string()),
+        channel_name: author.or(channel_name.map(str::to_string)),
+        // フィードには長さと配信状態が含まれない
+        duration: None,
+        timestamp: child_text(entry, ATOM_NS, "published").as_deref().and_then(parse_rfc3339),
+        thumbnail,
+        view_count,
+        live_status: None,
+        video_id,
+        url,
+    })
+}
+
+
+// --- 公開関数 ---
+
+/// チャンネルのAtomフィードから動画一覧を取り出す (新しい順)
+pub fn parse_channel_feed(xml: &str, channel_id: &str) -> Result<Vec<ChannelVideo>, String> {
+    let document = Document::parse(xml).map_err(|e| format!("Failed to parse feed: {}", e))?;
+    let feed = document.root_element();
+    if feed.tag_name().name() != "feed" {
+        return Err("Not an Atom feed".to_string());
+    }
+
+    let channel_name = child_text(feed, ATOM_NS, "title");
+    Ok(feed.children()
+        .filter(|n| n.tag_name().namespace() == Some(ATOM_NS) && n.tag_name().name() == "entry")
+        .filter_map(|entry| entry_to_video(entry, channel_id, channel_name.as_deref()))
+        .collect())
+}
+
+/// `{base_url}?channel_id=...` のフィードを取得する
+///
+/// フィードは最新15件程度しか含まないため、新着の有無を調べる用途に使う。
+pub async fn fetch_channel_feed(base_url: &str, channel_id: &str) -> Result<Vec<ChannelVideo>, String> {
+    let mut url = url::Url::parse(base_url).map_err(|e| format!("Invalid feed URL: {}", e))?;
+    url.query_pairs_mut().append_pair("channel_id", channel_id);
+
+    let response = CLIENT.get(url).send().await
+        .map_err(|e| format!("Failed to fetch feed: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Feed returned HTTP {}", response.status()));
+    }
+    let body = response.text().await.map_err(|e| format!("Failed to read feed: {}", e))?;
+
+    parse_channel_feed(&body, channel_id)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    const CHANNEL_ID: &str = "UCXuqSBlHAE6Xw-yeJA0Tunw";
+    const FEED: &str = include_str!("../../tests/fixtures/channel-feed.xml");
+
+    /// 1回だけ応答するHTTPサーバーを立て、フィードのベースURLと受け取ったリクエスト行を返す
+    async fn serve_once(status: &'static str, body: &'static str) -> (String, tokio::task::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}/feeds/videos.xml", listener.local_addr().unwrap());
+        let handle = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 1024];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = socket.read(&mut buf).await.unwrap();
+                if n == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buf[..n]);
+            }
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Type: text/xml; charset=UTF-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status, body.len(), body,
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8_lossy(&request).lines().next().unwrap_or_default().to_string()
+        });
+        (base_url, handle)
+    }
+
+    #[test]
+    fn parses_entries_in_feed_order() {
+        let videos = parse_channel_feed(FEED, CHANNEL_ID).unwrap();
+        let ids: Vec<&str> = videos.iter().map(|v| v.video_id.as_str()).collect();
+        // videoId の無いエントリは飛ばす
+        assert_eq!(ids, ["dQw4w9WgXcQ", "9bZkp7q19f0", "M7lc1UVf-VE"]);
+
+        let first = &videos[0];
+        assert_eq!(first.title, "This PC Build Went Wrong & Here's Why");
+        assert_eq!(first.url, "https://www.youtube.com/watch?v=dQw4w9WgXcQ");
+        assert_eq!(first.channel_id, CHANNEL_ID);
+        assert_eq!(first.channel_name.as_deref(), Some("Linus Tech Tips"));
+        assert_eq!(first.thumbnail.as_deref(), Some("https://i2.ytimg.com/vi/dQw4w9WgXcQ/hqdefault.jpg"));
+        assert_eq!(first.view_count, Some(1234567));
+        assert_eq!(first.duration, None);
+        assert_eq!(first.live_status, None);
+
+        // ショートはフィードのリンクをそのまま使う
+        assert_eq!(videos[1].url, "https://www.youtube.com/shorts/9bZkp7q19f0");
+        assert_eq!(videos[1].view_count, Some(0));
+    }
+
+    #[test]
+    fn parses_published_dates_with_offsets() {
+        let videos = parse_channel_feed(FEED, CHANNEL_ID).unwrap();
+        let timestamps: Vec<Option<u64>> = videos.iter().map(|v| v.timestamp).collect();
+        assert_eq!(timestamps, [Some(1706720408), Some(1706625000), Some(1706529600)]);
+
+        assert_eq!(parse_rfc3339("2024-01-31T17:00:08+00:00"), Some(1706720408));
+        assert_eq!(parse_rfc3339("2024-01-31T18:00:08+01:00"), Some(1706720408));
+        assert_eq!(parse_rfc3339("2024-01-31T17:00:08.123Z"), Some(1706720408));
+        assert_eq!(parse_rfc3339("2024-01-31"), None);
+        assert_eq!(parse_rfc3339("not a date at all"), None);
+    }
+
+    #[test]
+    fn fills_missing_fields_from_channel() {
+        let videos = parse_channel_feed(FEED, CHANNEL_ID).unwrap();
+        let premiere = &videos[2];
+        assert_eq!(premiere.title, "Upcoming Premiere");
+        assert_eq!(premiere.channel_id, CHANNEL_ID);
+        assert_eq!(premiere.channel_name.as_deref(), Some("Linus Tech Tips"));
+        assert_eq!(premiere.url, "https://www.youtube.com/watch?v=M7lc1UVf-VE");
+        assert_eq!(premiere.thumbnail, None);
+        assert_eq!(premiere.view_count, None);
+    }
+
+    #[test]
+    fn rejects_documents_that_are_not_feeds() {
+        assert!(parse_channel_feed("<html><body>Sign in</body></html>", CHANNEL_ID).is_err());
+        assert!(parse_channel_feed("<feed><entry>", CHANNEL_ID).is_err());
+        assert!(parse_channel_feed("", CHANNEL_ID).is_err());
+
+        let empty = r#"<feed xmlns="http://www.w3.org/2005/Atom"><title>Empty</title></feed>"#;
+        assert!(parse_channel_feed(empty, CHANNEL_ID).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn fetches_feed_with_channel_id() {
+        let (base_url, server) = serve_once("200 OK", FEED).await;
+        let videos = fetch_channel_feed(&base_url, CHANNEL_ID).await.unwrap();
+        assert_eq!(videos.len(), 3);
+        assert_eq!(videos[0].video_id, "dQw4w9WgXcQ");
+
+        let request_line = server.await.unwrap();
+        assert_eq!(request_line, format!("GET /feeds/videos.xml?channel_id={} HTTP/1.1", CHANNEL_ID));
+    }
+
+    #[tokio::test]
+    async fn error_status_is_returned_for_fallback() {
+        // 削除・非公開のチャンネルは404になり、呼び出し側はyt-dlpで取り直す
+        let (base_url, server) = serve_once("404 Not Found", "<!DOCTYPE html><html></html>").await;
+        let error = fetch_channel_feed(&base_url, CHANNEL_ID).await.unwrap_err();
+        assert!(error.contains("404"), "{}", error);
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn invalid_base_url_is_an_error() {
+        assert!(fetch_channel_feed("not a url", CHANNEL_ID).await.is_err());
+    }
+}
@@ -0,0 +1,279 @@
+use tauri::{AppHandle, Manager, State};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use serde::{Deserialize, Serialize};
+use anyhow::{Result, Context, anyhow};
+
+use crate::download::manager::{DownloadJob, DownloadManager, DownloadRequest};
+use crate::download::options::{AudioOptions, DownloadPreset};
+use crate::library::index::Library;
+use crate::subscription::video::ChannelVideo;
+
+// --- 定数定義 ---
+const RULES_FILE_NAME: &str = "download-rules.json";
+const OUTPUT_TEMPLATE: &str = "%(channel)s/%(title)s [%(id)s].%(ext)s";
+
+
+// --- 型定義 ---
+
+/// チャンネルごとの自動ダウンロードのルール
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChannelRule {
+    pub channel_id: String,
+    /// 新しい動画を自動でダウンロードする
+    pub auto_download: bool,
+    pub max_height: Option<u32>,
+    pub audio_only: bool,
+    /// いずれかを含むタイトルだけを対象にする (空なら全て)
+    pub include_keywords: Vec<String>,
+    /// いずれかを含むタイトルは対象外にする
+    pub exclude_keywords: Vec<String>,
+    /// 秒
+    pub min_duration: Option<u64>,
+    /// 秒
+    pub max_duration: Option<u64>,
+    /// このルールでダウンロードした動画を、ダウンロードした順に新しいものからN件だけ残す (1以上)
+    pub keep_last: Option<usize>,
+    pub preset: DownloadPreset,
+}
+
+impl Default for ChannelRule {
+    fn default() -> Self {
+        Self {
+            channel_id: String::new(),
+            auto_download: false,
+            max_height: None,
+            audio_only: false,
+            include_keywords: Vec::new(),
+            exclude_keywords: Vec::new(),
+            min_duration: None,
+            max_duration: None,
+            keep_last: None,
+            preset: DownloadPreset::Best,
+        }
+    }
+}
+
+/// チャンネルIDをキーにしたルールの保存先
+pub struct DownloadRules {
+    path: PathBuf,
+    rules: Mutex<HashMap<String, ChannelRule>>,
+}
+
+
+// --- プライベートヘルパー関数 ---
+
+fn contains_any(title: &str, keywords: &[String]) -> bool {
+    keywords.iter()
+        .filter(|k| !k.trim().is_empty())
+        .any(|k| title.contains(&k.trim().to_lowercase()))
+}
+
+impl ChannelRule {
+    fn validate(&self) -> Result<()> {
+        if self.keep_last == Some(0) {
+            return Err(anyhow!("残す件数は1以上にしてください"));
+        }
+        Ok(())
+    }
+
+    /// 動画がルールの条件に合うか
+    pub fn matches(&self, video: &ChannelVideo) -> bool {
+        if !self.auto_download {
+            return false;
+        }
+
+        let title = video.title.to_lowercase();
+        if !self.include_keywords.iter().all(|k| k.trim().is_empty()) && !contains_any(&title, &self.include_keywords) {
+            return false;
+        }
+        if contains_any(&title, &self.exclude_keywords) {
+            return false;
+        }
+
+        // 長さの条件がある場合、長さが分からない動画 (配信など) は対象外にする
+        if self.min_duration.is_some() || self.max_duration.is_some() {
+            let Some(duration) = video.duration else { return false };
+            if self.min_duration.is_some_and(|min| duration < min) {
+                return false;
+            }
+            if self.max_duration.is_some_and(|max| duration > max) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    fn to_request(&self, video: &ChannelVideo, library_dir: &std::path::Path) -> DownloadRequest {
+        let mut options = self.preset.options();
+        if self.max_height.is_some() {
+            options.max_height = self.max_height;
+        }
+        if self.audio_only && options.audio.is_none() {
+            options.audio = Some(AudioOptions::default());
+        }
+
+        DownloadRequest {
+            video_url: video.url.clone(),
+            output_path: library_dir.join(OUTPUT_TEMPLATE).to_string_lossy().to_string(),
+            format_id: None,
+            options,
+            from_rule: true,
+        }
+    }
+}
+
+
+impl DownloadRules {
+    pub fn new(app_handle: &AppHandle) -> Result<Self> {
+        let app_data_dir = app_handle.path().app_data_dir().context("アプリデータディレクトリの取得に失敗")?;
+        let path = app_data_dir.join(RULES_FILE_NAME);
+        let rules = match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_default(),
+            Err(_) => HashMap::new(),
+        };
+        Ok(Self { path, rules: Mutex::new(rules) })
+    }
+
+    fn save(&self, rules: &HashMap<String, ChannelRule>) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let temp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&temp_path, serde_json::to_string_pretty(rules)?)?;
+        std::fs::rename(&temp_path, &self.path)?;
+        Ok(())
+    }
+
+    pub fn list(&self) -> Vec<ChannelRule> {
+        self.rules.lock().unwrap().values().cloned().collect()
+    }
+
+    pub fn get(&self, channel_id: &str) -> Option<ChannelRule> {
+        self.rules.lock().unwrap().get(channel_id).cloned()
+    }
+
+    pub fn set(&self, rule: ChannelRule) -> Result<()> {
+        rule.validate()?;
+        let mut rules = self.rules.lock().unwrap();
+        rules.insert(rule.channel_id.clone(), rule);
+        self.save(&rules)
+    }
+
+    /// バックアップの復元用。`replace` なら全て置き換え、そうでなければルールの無いチャンネルの分だけ加える
+    pub fn restore(&self, restored: Vec<ChannelRule>, replace: bool) -> Result<usize> {
+        let mut rules = self.rules.lock().unwrap();
+        if replace {
+            rules.clear();
+        }
+        let mut added = 0;
+        for rule in restored {
+            if let Err(e) = rule.validate() {
+                println!("Skipping download rule for {}: {}", rule.channel_id, e);
+                continue;
+            }
+            if !rules.contains_key(&rule.channel_id) {
+                rules.insert(rule.channel_id.clone(), rule);
+                added += 1;
+            }
+        }
+        self.save(&rules)?;
+        Ok(added)
+    }
+
+    pub fn remove(&self, channel_id: &str) -> Result<()> {
+        let mut rules = self.rules.lock().unwrap();
+        rules.remove(channel_id);
+        self.save(&rules)
+    }
+}
+
+
+// --- 公開関数 ---
+
+/// 新しく見つかった動画にチャンネルのルールを適用し、条件に合うものをダウンロードキューに入れる
+pub fn apply_rules(app_handle: &AppHandle, channel_id: &str, videos: &[ChannelVideo]) -> Result<Vec<DownloadJob>> {
+    let Some(rule) = app_handle.state::<DownloadRules>().get(channel_id) else { return Ok(Vec::new()) };
+    if !rule.auto_download {
+        return Ok(Vec::new());
+    }
+
+    let manager = app_handle.state::<DownloadManager>();
+    let library = app_handle.state::<Library>();
+    let library_dir = library.library_dir();
+
+    let mut jobs = Vec::new();
+    for video in videos.iter().filter(|v| rule.matches(v)) {
+        // ダウンロード済み・キュー済みのものは入れない (アーカイブでも弾かれるが、一覧が増えないようにする)
+        if library.find(&video.video_id).is_some() || manager.is_queued(&video.url) {
+            continue;
+        }
+        println!("Auto-download rule matched: {} ({})", video.title, channel_id);
+        jobs.push(manager.enqueue(rule.to_request(video, &library_dir))?);
+    }
+
+    // 0 は保存時に弾いているが、古い設定ファイルでチャンネルの動画を全て消さないようにする
+    if let Some(keep_last) = rule.keep_last.filter(|n| *n > 0) {
+        enforce_keep_last(&library, channel_id, keep_last);
+    }
+
+    Ok(jobs)
+}
+
+/// ルールでダウンロードしたチャンネルの動画を、ダウンロードした順に新しいものからN件残して削除する
+///
+/// 手動でダウンロードしたものは対象にしない。
+fn enforce_keep_last(library: &Library, channel_id: &str, keep_last: usize) {
+    let mut entries: Vec<_> = library.list().into_iter()
+        .filter(|e| e.from_rule && e.channel_id.as_deref() == Some(channel_id))
+        .collect();
+    entries.sort_by_key(|e| std::cmp::Reverse(e.downloaded_at));
+
+    for entry in entries.into_iter().skip(keep_last) {
+        println!("Removing old download by keep-last rule: {}", entry.title);
+        // 1件消せなくても残りのチャンネル・動画の処理は続ける
+        if let Err(e) = library.remove(&entry.video_id, true) {
+            println!("Failed to remove {}: {}", entry.video_id, e);
+        }
+    }
+}
+
+
+// --- Tauri Commands ---
+
+#[tauri::command]
+pub fn get_download_rules(rules: State<'_, DownloadRules>) -> Result<Vec<ChannelRule>, String> {
+    Ok(rules.list())
+}
+
+#[tauri::command]
+pub fn set_download_rule(rules: State<'_, DownloadRules>, rule: ChannelRule) -> Result<(), String> {
+    rules.set(rule).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn remove_download_rule(rules: State<'_, DownloadRules>, channel_id: String) -> Result<(), String> {
+    rules.remove(&channel_id).map_err(|e| e.to_string())
+}
+
+/// 新しく見つかった動画にルールを適用し、追加したダウンロードを返す
+#[tauri::command]
+pub fn apply_download_rules(
+    app_handle: AppHandle,
+    channel_id: String,
+    videos: Vec<ChannelVideo>,
+) -> Result<Vec<DownloadJob>, String> {
+    apply_rules(&app_handle, &channel_id, &videos).map_err(|e| e.to_string())
+}
+
+/// ダウンロードせずに、ルールに合う動画IDを返す (設定画面のプレビュー用)
+#[tauri::command]
+pub fn preview_download_rule(rule: ChannelRule, videos: Vec<ChannelVideo>) -> Vec<String> {
+    videos.iter()
+        .filter(|v| rule.matches(v))
+        .map(|v| v.video_id.clone())
+        .collect()
+}
@@ -0,0 +1,370 @@
+use tauri::{AppHandle, Manager, Emitter, State};
+use tauri_plugin_notification::NotificationExt;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use anyhow::{Result, Context, anyhow};
+use futures::stream::{self, StreamExt};
+use tokio::sync::{Mutex as AsyncMutex, Notify};
+
+use crate::db::search::{record_video_metadata, VideoMetadata};
+use crate::db::subscriptions::{get_followed_channels, FollowedChannel};
+use crate::subscription::fetch::fetch_channel_videos;
+use crate::subscription::rss::{fetch_channel_feed, DEFAULT_FEED_BASE_URL};
+use crate::subscription::rules::apply_rules;
+use crate::subscription::video::ChannelVideo;
+
+// --- 定数定義 ---
+const SETTINGS_FILE_NAME: &str = "subscription-refresh.json";
+const STATE_FILE_NAME: &str = "subscription-state.json";
+const FETCH_CONCURRENCY: usize = 4;
+/// チャンネルごとに覚えておく既読IDの数
+const MAX_SEEN_PER_CHANNEL: usize = 200;
+const MAX_STORED_NEW_VIDEOS: usize = 500;
+/// 起動直後はフロントエンドの読み込みと重ならないように少し待つ
+const STARTUP_DELAY: Duration = Duration::from_secs(30);
+
+
+// --- 型定義 ---
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RefreshSettings {
+    pub enabled: bool,
+    pub interval_minutes: u64,
+    /// デスクトップ通知を出す
+    pub notify: bool,
+    /// 1回の確認でチャンネルごとに取得する動画数
+    pub videos_per_channel: u32,
+    /// yt-dlpの前にAtomフィードで新着の有無を調べる
+    pub use_feed: bool,
+    /// `?channel_id=` を付けて取得するフィードのURL
+    pub feed_base_url: String,
+}
+
+impl Default for RefreshSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_minutes: 30,
+            notify: true,
+            videos_per_channel: 15,
+            use_feed: true,
+            feed_base_url: DEFAULT_FEED_BASE_URL.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct RefreshState {
+    /// チャンネルID → 確認済みの動画ID (新しい順)
+    last_seen: HashMap<String, Vec<String>>,
+    /// 見つかった新着動画 (新しい順)
+    new_videos: Vec<ChannelVideo>,
+    last_refreshed_at: Option<u64>,
+    /// 失敗したときも含めて最後に確認を始めた時刻 (失敗しても次の間隔まで待つ)
+    last_attempted_at: Option<u64>,
+}
+
+/// 取得した動画と、新着扱いせずに確認済みにするID
+type ChannelFetch = (Vec<ChannelVideo>, Vec<String>);
+
+/// フォロー中のチャンネルを定期的に確認するスケジューラー
+pub struct SubscriptionScheduler {
+    app_handle: AppHandle,
+    settings_path: PathBuf,
+    state_path: PathBuf,
+    settings: Mutex<RefreshSettings>,
+    state: Mutex<RefreshState>,
+    /// 設定変更時にタイマーを作り直す
+    wake: Notify,
+    /// 手動と定期の確認が重ならないようにする
+    refresh_lock: AsyncMutex<()>,
+}
+
+
+// --- プライベートヘルパー関数 ---
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+fn read_json<T: serde::de::DeserializeOwned + Default>(path: &PathBuf) -> T {
+    std::fs::read_to_string(path).ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn write_json<T: Serialize>(path: &PathBuf, value: &T) -> Result<()> {
+    let temp_path = path.with_extension("json.tmp");
+    std::fs::write(&temp_path, serde_json::to_string_pretty(value)?)?;
+    std::fs::rename(&temp_path, path)?;
+    Ok(())
+}
+
+
+impl SubscriptionScheduler {
+    pub fn new(app_handle: &AppHandle) -> Result<Self> {
+        let app_data_dir = app_handle.path().app_data_dir().context("アプリデータディレクトリの取得に失敗")?;
+        std::fs::create_dir_all(&app_data_dir)?;
+        let settings_path = app_data_dir.join(SETTINGS_FILE_NAME);
+        let state_path = app_data_dir.join(STATE_FILE_NAME);
+
+        Ok(Self {
+            app_handle: app_handle.clone(),
+            settings: Mutex::new(read_json(&settings_path)),
+            state: Mutex::new(read_json(&state_path)),
+            settings_path,
+            state_path,
+            wake: Notify::new(),
+            refresh_lock: AsyncMutex::new(()),
+        })
+    }
+
+    /// バックグラウンドのループを開始する
+    pub fn start(app_handle: &AppHandle) {
+        let app_handle = app_handle.clone();
+        tauri::async_runtime::spawn(async move {
+            let scheduler = app_handle.state::<SubscriptionScheduler>();
+            tokio::time::sleep(STARTUP_DELAY).await;
+
+            loop {
+                let Some(wait) = scheduler.time_until_next_refresh() else {
+                    // 無効の間は設定が変わるまで待つ
+                    scheduler.wake.notified().await;
+                    continue;
+                };
+
+                tokio::select! {
+                    _ = tokio::time::sleep(wait) => {
+                        // 手動の確認が実行中なら終わるのを待ち、まだ必要なときだけ確認する
+                        let _guard = scheduler.refresh_lock.lock().await;
+                        if scheduler.time_until_next_refresh().is_some_and(|wait| wait.is_zero()) {
+                            if let Err(e) = scheduler.refresh_locked().await {
+                                println!("Failed to refresh subscriptions: {}", e);
+                            }
+                        }
+                    }
+                    _ = scheduler.wake.notified() => {}
+                }
+            }
+        });
+    }
+
+    fn time_until_next_refresh(&self) -> Option<Duration> {
+        let settings = self.settings.lock().unwrap().clone();
+        if !settings.enabled {
+            return None;
+        }
+        let interval = settings.interval_minutes.max(1) * 60;
+        let state = self.state.lock().unwrap();
+        let last = state.last_refreshed_at.max(state.last_attempted_at).unwrap_or(0);
+        let next = last + interval;
+        Some(Duration::from_secs(next.saturating_sub(now_secs())))
+    }
+
+    pub fn settings(&self) -> RefreshSettings {
+        self.settings.lock().unwrap().clone()
+    }
+
+    pub fn set_settings(&self, settings: RefreshSettings) -> Result<()> {
+        write_json(&self.settings_path, &settings)?;
+        *self.settings.lock().unwrap() = settings;
+        self.wake.notify_one();
+        Ok(())
+    }
+
+    pub fn new_videos(&self) -> Vec<ChannelVideo> {
+        self.state.lock().unwrap().new_videos.clone()
+    }
+
+    pub fn clear_new_videos(&self) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.new_videos.clear();
+        write_json(&self.state_path, &*state)
+    }
+
+    /// 確認済みでない動画IDがあるか (初回のチャンネルは記録するだけなので無いとみなす)
+    fn has_unseen(&self, channel_id: &str, videos: &[ChannelVideo]) -> bool {
+        let state = self.state.lock().unwrap();
+        let Some(seen) = state.last_seen.get(channel_id) else { return false };
+        videos.iter().any(|v| !seen.contains(&v.video_id))
+    }
+
+    /// チャンネル1件の最新動画を取得する
+    ///
+    /// フィードに新しいIDが無ければyt-dlpを起動せずにフィードの結果を使う。
+    /// フィードにだけ載り、URLでショートと分かるものは新着扱いせずに確認済みにする。
+    /// それ以外でyt-dlpの一覧に無いものは、一覧への反映が遅れているだけのことがあるので次の確認に回す。
+    async fn fetch_channel(&self, channel: &FollowedChannel, limit: u32) -> Result<ChannelFetch> {
+        let settings = self.settings();
+        let mut feed_shorts = Vec::new();
+
+        if settings.use_feed {
+            match fetch_channel_feed(&settings.feed_base_url, &channel.id).await {
+                Ok(feed_videos) if !self.has_unseen(&channel.id, &feed_videos) => {
+                    return Ok((feed_videos, Vec::new()));
+                }
+                Ok(feed_videos) => {
+                    // 長さや配信状態はフィードに無いので、yt-dlpで取り直す
+                    feed_shorts = feed_videos.into_iter()
+                        .filter(|v| v.url.contains("/shorts/"))
+                        .map(|v| v.video_id)
+                        .collect();
+                }
+                Err(e) => println!("Feed check failed for {}, falling back to yt-dlp: {}", channel.id, e),
+            }
+        }
+
+        let videos = fetch_channel_videos(&self.app_handle, &channel.id, limit).await.map_err(|e| anyhow!(e))?;
+        feed_shorts.retain(|id| !videos.iter().any(|v| &v.video_id == id));
+        Ok((videos, feed_shorts))
+    }
+
+    /// 確認済みIDと比較して新着を返し、確認済みIDを更新する
+    fn diff_and_record(&self, channel_id: &str, videos: &[ChannelVideo], also_seen: &[String]) -> Vec<ChannelVideo> {
+        let mut state = self.state.lock().unwrap();
+        let Some(seen) = state.last_seen.get_mut(channel_id) else {
+            // 初回は既存の動画を新着扱いしない
+            let ids = videos.iter().map(|v| v.video_id.clone()).collect();
+            state.last_seen.insert(channel_id.to_string(), ids);
+            return Vec::new();
+        };
+
+        let seen_set: HashSet<&String> = seen.iter().collect();
+        let new: Vec<ChannelVideo> = videos.iter()
+            .filter(|v| !seen_set.contains(&v.video_id))
+            .cloned()
+            .collect();
+
+        let mut updated: Vec<String> = new.iter().map(|v| v.video_id.clone()).collect();
+        updated.extend(also_seen.iter().filter(|id| !seen_set.contains(id) && !updated.contains(id)).cloned().collect::<Vec<_>>());
+        updated.append(seen);
+        updated.truncate(MAX_SEEN_PER_CHANNEL);
+        *seen = updated;
+
+        new
+    }
+
+    /// フォロー中の全チャンネルを確認し、新着動画を返す
+    pub async fn refresh_all(&self) -> Result<Vec<ChannelVideo>> {
+        let Ok(_guard) = self.refresh_lock.try_lock() else {
+            return Err(anyhow!("購読の更新は既に実行中です"));
+        };
+        self.refresh_locked().await
+    }
+
+    /// `refresh_lock` を持った状態で呼ぶ
+    async fn refresh_locked(&self) -> Result<Vec<ChannelVideo>> {
+        self.state.lock().unwrap().last_attempted_at = Some(now_secs());
+        self.refresh_all_inner().await
+    }
+
+    async fn refresh_all_inner(&self) -> Result<Vec<ChannelVideo>> {
+        let channels = get_followed_channels(&self.app_handle)?;
+        let limit = self.settings().videos_per_channel;
+        println!("Refreshing {} subscriptions...", channels.len());
+
+        let results: Vec<(FollowedChannel, Result<ChannelFetch>)> = stream::iter(channels)
+            .map(|channel| async move {
+                let result = self.fetch_channel(&channel, limit).await;
+                (channel, result)
+            })
+            .buffer_unordered(FETCH_CONCURRENCY)
+            .collect()
+            .await;
+
+        let mut all_new = Vec::new();
+        for (channel, result) in results {
+            let (videos, also_seen) = match result {
+                Ok(fetched) => fetched,
+                Err(e) => {
+                    println!("Failed to refresh channel {}: {}", channel.id, e);
+                    continue;
+                }
+            };
+
+            let metadata: Vec<VideoMetadata> = videos.iter().map(VideoMetadata::from).collect();
+            record_video_metadata(&self.app_handle, &metadata);
+
+            let new = self.diff_and_record(&channel.id, &videos, &also_seen);
+            if new.is_empty() {
+                continue;
+            }
+            if let Err(e) = apply_rules(&self.app_handle, &channel.id, &new) {
+                println!("Failed to apply download rules for {}: {}", channel.id, e);
+            }
+            all_new.extend(new);
+        }
+        all_new.sort_by_key(|v| std::cmp::Reverse(v.timestamp.unwrap_or(0)));
+
+        {
+            let mut state = self.state.lock().unwrap();
+            let mut stored = all_new.clone();
+            stored.append(&mut state.new_videos);
+            stored.truncate(MAX_STORED_NEW_VIDEOS);
+            state.new_videos = stored;
+            state.last_refreshed_at = Some(now_secs());
+            write_json(&self.state_path, &*state)?;
+        }
+
+        if !all_new.is_empty() {
+            self.app_handle.emit("new_videos", &all_new)?;
+            if self.settings().notify {
+                self.notify(&all_new);
+            }
+        }
+
+        println!("Subscription refresh finished: {} new videos", all_new.len());
+        Ok(all_new)
+    }
+
+    fn notify(&self, videos: &[ChannelVideo]) {
+        let (title, body) = match videos {
+            [video] => (
+                video.channel_name.clone().unwrap_or_else(|| "新着動画".to_string()),
+                video.title.clone(),
+            ),
+            _ => (
+                format!("新着動画 {}件", videos.len()),
+                videos.iter().take(3).map(|v| v.title.as_str()).collect::<Vec<_>>().join("\n"),
+            ),
+        };
+
+        if let Err(e) = self.app_handle.notification().builder().title(title).body(body).show() {
+            println!("Failed to show notification: {}", e);
+        }
+    }
+}
+
+
+// --- Tauri Commands ---
+
+#[tauri::command]
+pub fn get_refresh_settings(scheduler: State<'_, SubscriptionScheduler>) -> Result<RefreshSettings, String> {
+    Ok(scheduler.settings())
+}
+
+#[tauri::command]
+pub fn set_refresh_settings(scheduler: State<'_, SubscriptionScheduler>, settings: RefreshSettings) -> Result<(), String> {
+    scheduler.set_settings(settings).map_err(|e| e.to_string())
+}
+
+/// 今すぐ全チャンネルを確認する
+#[tauri::command]
+pub async fn refresh_subscriptions(scheduler: State<'_, SubscriptionScheduler>) -> Result<Vec<ChannelVideo>, String> {
+    scheduler.refresh_all().await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_new_videos(scheduler: State<'_, SubscriptionScheduler>) -> Result<Vec<ChannelVideo>, String> {
+    Ok(scheduler.new_videos())
+}
+
+#[tauri::command]
+pub fn clear_new_videos(scheduler: State<'_, SubscriptionScheduler>) -> Result<(), String> {
+    scheduler.clear_new_videos().map_err(|e| e.to_string())
+}
@@ -0,0 +1,213 @@
+use tauri::AppHandle;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::Value;
+
+use crate::db::history::{merge_history, HistoryEntry, HistoryImportReport};
+use crate::subscription::fetch::days_from_civil;
+use crate::subscription::import::{channel_id_from_url, import_entries, SubscriptionEntry, SubscriptionImportReport};
+use crate::subscription::rss::parse_rfc3339;
+
+// --- 定数定義 ---
+static CONTENT_CELL_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?s)<div class="content-cell[^"]*mdl-typography--body-1">(.*?)</div>"#).unwrap()
+});
+static LINK_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?s)<a href="([^"]+)">(.*?)</a>"#).unwrap()
+});
+static TAG_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"<[^>]+>").unwrap());
+// "Jan 31, 2024, 1:23:45 PM JST"
+static EN_DATE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"([A-Z][a-z]{2}) (\d{1,2}), (\d{4}), (\d{1,2}):(\d{2}):(\d{2})\s*([AP]M)?\s*([A-Z]{2,5})?").unwrap()
+});
+// "2024/01/31 13:23:45 JST"
+static NUMERIC_DATE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(\d{4})/(\d{1,2})/(\d{1,2}) (\d{1,2}):(\d{2}):(\d{2})\s*([A-Z]{2,5})?").unwrap()
+});
+
+const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+
+// --- プライベートヘルパー関数 ---
+
+fn unescape_html(value: &str) -> String {
+    value
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+        .trim()
+        .to_string()
+}
+
+/// "Watched 〇〇" / "〇〇 を視聴しました" から動画タイトルを取り出す
+fn strip_watched(title: &str) -> String {
+    let title = title.trim();
+    let title = title.strip_prefix("Watched ").unwrap_or(title);
+    let title = title.strip_suffix(" を視聴しました").unwrap_or(title);
+    title.trim().to_string()
+}
+
+/// タイムゾーンの略称を秒のオフセットにする (分からないものはUTC扱い)
+fn zone_offset(zone: Option<&str>) -> i64 {
+    let hours = match zone {
+        Some("JST") | Some("KST") => 9,
+        Some("CET") => 1,
+        Some("CEST") => 2,
+        Some("EST") => -5,
+        Some("EDT") => -4,
+        Some("PST") => -8,
+        Some("PDT") => -7,
+        _ => 0,
+    };
+    hours * 3600
+}
+
+/// ダブルクォートで囲まれた値に対応したCSVの1行の分割
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                current.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    fields.push(current);
+    fields
+}
+
+/// HTML版の日時表記をミリ秒に変換する
+fn parse_html_date(text: &str) -> Option<u64> {
+    let (year, month, day, mut hour, minute, second, zone): (i64, i64, i64, i64, i64, i64, _) = if let Some(c) = EN_DATE_RE.captures(text) {
+        let month = MONTHS.iter().position(|m| *m == &c[1])? as i64 + 1;
+        let mut hour: i64 = c[4].parse().ok()?;
+        match c.get(7).map(|m| m.as_str()) {
+            Some("PM") if hour < 12 => hour += 12,
+            Some("AM") if hour == 12 => hour = 0,
+            _ => {}
+        }
+        (c[3].parse().ok()?, month, c[2].parse().ok()?, hour, c[5].parse().ok()?, c[6].parse().ok()?, c.get(8))
+    } else {
+        let c = NUMERIC_DATE_RE.captures(text)?;
+        (c[1].parse().ok()?, c[2].parse().ok()?, c[3].parse().ok()?, c[4].parse().ok()?, c[5].parse().ok()?, c[6].parse().ok()?, c.get(7))
+    };
+    hour %= 24;
+
+    let seconds = days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60 + second
+        - zone_offset(zone.map(|m| m.as_str()));
+    u64::try_from(seconds * 1000).ok()
+}
+
+
+// --- 公開関数 ---
+
+/// `subscriptions.csv` (チャンネルID,チャンネルURL,チャンネル名) を読み込む
+///
+/// 1行目は言語によって見出しが変わるので読み飛ばす。
+pub fn parse_subscriptions_csv(content: &str) -> Vec<SubscriptionEntry> {
+    content.trim_start_matches('\u{feff}')
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields = split_csv_line(line);
+            let id = fields.first()?.trim();
+            if id.is_empty() {
+                return None;
+            }
+            let url = fields.get(1).map(|u| u.trim().to_string()).filter(|u| !u.is_empty());
+            Some(SubscriptionEntry {
+                title: fields.get(2).map(|t| t.trim().to_string()).unwrap_or_default(),
+                xml_url: None,
+                html_url: Some(url.unwrap_or_else(|| format!("https://www.youtube.com/channel/{}", id))),
+            })
+        })
+        .collect()
+}
+
+/// `watch-history.json` を読み込む (削除された動画や広告は除く)
+pub fn parse_watch_history_json(content: &str) -> Result<Vec<HistoryEntry>, String> {
+    let items: Vec<Value> = serde_json::from_str(content.trim_start_matches('\u{feff}'))
+        .map_err(|e| format!("Failed to parse watch history: {}", e))?;
+
+    let is_ad = |item: &Value| item["details"].as_array()
+        .is_some_and(|details| details.iter().any(|d| d["name"].as_str().is_some_and(|n| n.contains("Google"))));
+
+    Ok(items.iter()
+        .filter(|item| !is_ad(item))
+        .filter_map(|item| {
+            let url = item["titleUrl"].as_str()?.to_string();
+            let channel = &item["subtitles"][0];
+            Some(HistoryEntry {
+                title: strip_watched(item["title"].as_str().unwrap_or("")),
+                id: channel["url"].as_str().and_then(channel_id_from_url).unwrap_or_default(),
+                channel_name: channel["name"].as_str().unwrap_or("").to_string(),
+                timestamp: item["time"].as_str().and_then(parse_rfc3339).map(|s| s * 1000).unwrap_or(0),
+                url,
+            })
+        })
+        .collect())
+}
+
+/// HTML版の `watch-history.html` を読み込む
+///
+/// 日時は言語設定ごとに書式が違うため、英語と "2024/01/31" 形式だけ読み取り、それ以外は0になる。
+pub fn parse_watch_history_html(content: &str) -> Vec<HistoryEntry> {
+    CONTENT_CELL_RE.captures_iter(content)
+        .filter_map(|cell| {
+            let cell = &cell[1];
+            let mut links = LINK_RE.captures_iter(cell);
+            let video = links.next()?;
+            let url = unescape_html(&video[1]);
+            if !url.contains("watch?v=") {
+                return None;
+            }
+            let channel = links.next();
+
+            let date_text = cell.rsplit("<br>")
+                .map(|part| unescape_html(&TAG_RE.replace_all(part, "")))
+                .find(|part| !part.is_empty())
+                .unwrap_or_default();
+
+            Some(HistoryEntry {
+                title: strip_watched(&unescape_html(&TAG_RE.replace_all(&video[2], ""))),
+                id: channel.as_ref().and_then(|c| channel_id_from_url(&unescape_html(&c[1]))).unwrap_or_default(),
+                channel_name: channel.map(|c| unescape_html(&c[2])).unwrap_or_default(),
+                timestamp: parse_html_date(&date_text).unwrap_or(0),
+                url,
+            })
+        })
+        .collect()
+}
+
+
+// --- Tauri Commands ---
+
+/// Takeoutの `subscriptions.csv` からフォローを取り込む
+#[tauri::command]
+pub async fn import_takeout_subscriptions(app_handle: AppHandle, source_path: String) -> Result<SubscriptionImportReport, String> {
+    let content = std::fs::read_to_string(&source_path).map_err(|e| e.to_string())?;
+    import_entries(&app_handle, parse_subscriptions_csv(&content)).await
+}
+
+/// Takeoutの `watch-history.json` または `watch-history.html` から履歴を取り込む
+#[tauri::command]
+pub fn import_takeout_history(app_handle: AppHandle, source_path: String) -> Result<HistoryImportReport, String> {
+    let content = std::fs::read_to_string(&source_path).map_err(|e| e.to_string())?;
+    let entries = if content.trim_start_matches('\u{feff}').trim_start().starts_with('[') {
+        parse_watch_history_json(&content)?
+    } else {
+        parse_watch_history_html(&content)
+    };
+    merge_history(&app_handle, entries).map_err(|e| e.to_string())
+}
@@ -0,0 +1,19 @@
+use serde::{Deserialize, Serialize};
+
+/// チャンネルの動画1件 (購読の更新確認・フィード・自動ダウンロードで共通)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChannelVideo {
+    pub video_id: String,
+    pub url: String,
+    pub title: String,
+    pub channel_id: String,
+    pub channel_name: Option<String>,
+    /// 秒
+    pub duration: Option<u64>,
+    /// 投稿日時 (UNIX秒)
+    pub timestamp: Option<u64>,
+    pub thumbnail: Option<String>,
+    pub view_count: Option<u64>,
+    /// "not_live" | "is_live" | "is_upcoming" | "was_live" など
+    pub live_status: Option<String>,
+}
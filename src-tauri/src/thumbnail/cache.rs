@@ -0,0 +1,329 @@
+use tauri::{AppHandle, Manager, State};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::SystemTime;
+use serde::{Deserialize, Serialize};
+use anyhow::{Result, Context};
+use sha2::{Sha256, Digest};
+use futures::stream::{self, StreamExt};
+
+// --- 定数定義 ---
+const CACHE_DIR_NAME: &str = "thumbnails";
+const CONFIG_FILE_NAME: &str = "thumbnail-cache.json";
+const DEFAULT_BUDGET_BYTES: u64 = 256 * 1024 * 1024;
+const PREFETCH_CONCURRENCY: usize = 4;
+
+
+// --- 型定義 ---
+
+/// サムネイルのサイズ違い
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ThumbnailVariant {
+    Small,
+    #[default]
+    Medium,
+    Large,
+    /// URLをそのまま使う
+    Original,
+}
+
+impl ThumbnailVariant {
+    const ALL: [ThumbnailVariant; 4] = [Self::Large, Self::Medium, Self::Small, Self::Original];
+
+    /// i.ytimg.com の動画サムネイル名
+    fn video_thumbnail_name(&self) -> Option<&'static str> {
+        match self {
+            Self::Small => Some("mqdefault"),
+            Self::Medium => Some("hqdefault"),
+            Self::Large => Some("maxresdefault"),
+            Self::Original => None,
+        }
+    }
+
+    /// yt3.ggpht.com のアイコンのピクセルサイズ
+    fn avatar_size(&self) -> Option<u32> {
+        match self {
+            Self::Small => Some(88),
+            Self::Medium => Some(240),
+            Self::Large => Some(900),
+            Self::Original => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheConfig {
+    budget_bytes: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ThumbnailCacheStats {
+    pub directory: String,
+    pub file_count: usize,
+    pub total_bytes: u64,
+    pub budget_bytes: u64,
+}
+
+/// アプリデータディレクトリに置くサムネイル・アイコンのキャッシュ
+pub struct ThumbnailCache {
+    dir: PathBuf,
+    config_path: PathBuf,
+    http_client: reqwest::Client,
+    total_bytes: AtomicU64,
+    budget_bytes: AtomicU64,
+}
+
+
+// --- プライベートヘルパー関数 ---
+
+/// サイズ違いに応じてURLを書き換える
+fn variant_url(url: &str, variant: ThumbnailVariant) -> String {
+    let Ok(mut parsed) = url::Url::parse(url) else { return url.to_string() };
+    let host = parsed.host_str().unwrap_or_default().to_string();
+
+    if host.ends_with("ytimg.com") {
+        // https://i.ytimg.com/vi/<id>/<name>.jpg?sqp=... の形式
+        let segments: Vec<String> = parsed.path_segments()
+            .map(|s| s.map(str::to_string).collect())
+            .unwrap_or_default();
+        if let (Some(name), [kind, id, ..]) = (variant.video_thumbnail_name(), segments.as_slice()) {
+            if kind.starts_with("vi") {
+                return format!("https://i.ytimg.com/vi/{}/{}.jpg", id, name);
+            }
+        }
+    } else if host.ends_with("ggpht.com") || host.ends_with("googleusercontent.com") {
+        // https://yt3.ggpht.com/...=s900-c-k-c0x00ffffff-no-rj の形式
+        if let Some(size) = variant.avatar_size() {
+            let path = parsed.path().to_string();
+            if let Some(pos) = path.rfind("=s") {
+                let rest = &path[pos + 2..];
+                let digits = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+                if digits > 0 {
+                    let new_path = format!("{}=s{}{}", &path[..pos], size, &rest[digits..]);
+                    parsed.set_path(&new_path);
+                    return parsed.to_string();
+                }
+            }
+        }
+    }
+
+    url.to_string()
+}
+
+/// URLからキャッシュファイル名を決める
+fn cache_file_name(url: &str) -> String {
+    let hash = hex::encode(Sha256::digest(url.as_bytes()));
+    let ext = url::Url::parse(url).ok()
+        .and_then(|u| Path::new(u.path()).extension().map(|e| e.to_string_lossy().to_lowercase()))
+        .filter(|e| matches!(e.as_str(), "jpg" | "jpeg" | "png" | "webp"))
+        .unwrap_or_else(|| "jpg".to_string());
+    format!("{}.{}", &hash[..32], ext)
+}
+
+/// キャッシュディレクトリ内のファイルを (パス, サイズ, 最終アクセス) で列挙する
+fn list_cache_files(dir: &Path) -> Result<Vec<(PathBuf, u64, SystemTime)>> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_file() && !entry.file_name().to_string_lossy().ends_with(".tmp") {
+            files.push((entry.path(), metadata.len(), metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH)));
+        }
+    }
+    Ok(files)
+}
+
+/// LRU用に最終アクセス時刻として更新日時を更新する
+fn touch(path: &Path) {
+    if let Ok(file) = std::fs::File::options().write(true).open(path) {
+        let _ = file.set_modified(SystemTime::now());
+    }
+}
+
+
+impl ThumbnailCache {
+    pub fn new(app_handle: &AppHandle) -> Result<Self> {
+        let app_data_dir = app_handle.path().app_data_dir().context("アプリデータディレクトリの取得に失敗")?;
+        let dir = app_data_dir.join(CACHE_DIR_NAME);
+        std::fs::create_dir_all(&dir)?;
+
+        let config_path = app_data_dir.join(CONFIG_FILE_NAME);
+        let budget_bytes = std::fs::read_to_string(&config_path).ok()
+            .and_then(|s| serde_json::from_str::<CacheConfig>(&s).ok())
+            .map(|c| c.budget_bytes)
+            .unwrap_or(DEFAULT_BUDGET_BYTES);
+
+        let total_bytes = list_cache_files(&dir)?.iter().map(|(_, size, _)| size).sum();
+
+        // フロントエンドからasset protocolで読めるようにする
+        app_handle.asset_protocol_scope()
+            .allow_directory(&dir, false)
+            .context("asset protocolのスコープ設定に失敗")?;
+
+        Ok(Self {
+            dir,
+            config_path,
+            http_client: reqwest::Client::new(),
+            total_bytes: AtomicU64::new(total_bytes),
+            budget_bytes: AtomicU64::new(budget_bytes),
+        })
+    }
+
+    fn cached_path(&self, url: &str) -> PathBuf {
+        self.dir.join(cache_file_name(url))
+    }
+
+    /// キャッシュ済みならそのパスを、無ければダウンロードして保存したパスを返す
+    pub async fn get(&self, url: &str, variant: ThumbnailVariant) -> Result<PathBuf> {
+        let target_url = variant_url(url, variant);
+        let path = self.cached_path(&target_url);
+        if path.exists() {
+            touch(&path);
+            return Ok(path);
+        }
+
+        match self.download(&target_url, &path).await {
+            Ok(()) => Ok(path),
+            Err(e) => {
+                // maxresdefault が無い動画やオフライン時は他のサイズで代用する
+                ThumbnailVariant::ALL.iter()
+                    .map(|v| self.cached_path(&variant_url(url, *v)))
+                    .find(|p| p.exists())
+                    .ok_or(e)
+            }
+        }
+    }
+
+    async fn download(&self, url: &str, path: &Path) -> Result<()> {
+        let response = self.http_client.get(url).send().await?.error_for_status()?;
+        let bytes = response.bytes().await?;
+
+        // 書き込み途中のファイルを読まれないように一時ファイルから移動する
+        let temp_path = path.with_extension(format!("{}.tmp", uuid::Uuid::new_v4()));
+        std::fs::write(&temp_path, &bytes)?;
+        // 同じファイルを上書きする場合は古いサイズを差し引く
+        let replaced_bytes = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        std::fs::rename(&temp_path, path)?;
+
+        let added_bytes = bytes.len() as u64;
+        let _ = self.total_bytes.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |total| {
+            Some(total.saturating_sub(replaced_bytes).saturating_add(added_bytes))
+        });
+        self.evict_if_needed()?;
+        Ok(())
+    }
+
+    /// 容量上限を超えていれば最終アクセスが古いものから削除する
+    fn evict_if_needed(&self) -> Result<()> {
+        let budget = self.budget_bytes.load(Ordering::SeqCst);
+        if self.total_bytes.load(Ordering::SeqCst) <= budget {
+            return Ok(());
+        }
+
+        let mut files = list_cache_files(&self.dir)?;
+        files.sort_by_key(|(_, _, accessed)| *accessed);
+        let mut total: u64 = files.iter().map(|(_, size, _)| size).sum();
+
+        for (path, size, _) in files {
+            if total <= budget {
+                break;
+            }
+            if std::fs::remove_file(&path).is_ok() {
+                total -= size;
+            }
+        }
+
+        self.total_bytes.store(total, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// フィード表示前にまとめて取得しておく
+    pub async fn prefetch(&self, urls: Vec<String>, variant: ThumbnailVariant) {
+        stream::iter(urls)
+            .for_each_concurrent(PREFETCH_CONCURRENCY, |url| async move {
+                if let Err(e) = self.get(&url, variant).await {
+                    println!("Failed to prefetch thumbnail {}: {}", url, e);
+                }
+            })
+            .await;
+    }
+
+    pub fn set_budget(&self, budget_bytes: u64) -> Result<()> {
+        self.budget_bytes.store(budget_bytes, Ordering::SeqCst);
+        std::fs::write(&self.config_path, serde_json::to_string(&CacheConfig { budget_bytes })?)?;
+        self.evict_if_needed()
+    }
+
+    pub fn clear(&self) -> Result<()> {
+        for (path, _, _) in list_cache_files(&self.dir)? {
+            std::fs::remove_file(path)?;
+        }
+        self.total_bytes.store(0, Ordering::SeqCst);
+        Ok(())
+    }
+
+    pub fn stats(&self) -> Result<ThumbnailCacheStats> {
+        let files = list_cache_files(&self.dir)?;
+        Ok(ThumbnailCacheStats {
+            directory: self.dir.to_string_lossy().to_string(),
+            file_count: files.len(),
+            total_bytes: files.iter().map(|(_, size, _)| size).sum(),
+            budget_bytes: self.budget_bytes.load(Ordering::SeqCst),
+        })
+    }
+}
+
+/// フィード取得後にバックグラウンドでサムネイルを先読みする
+pub fn spawn_prefetch(app_handle: &AppHandle, urls: Vec<String>, variant: ThumbnailVariant) {
+    let urls: Vec<String> = urls.into_iter().filter(|u| u.starts_with("http")).collect();
+    if urls.is_empty() {
+        return;
+    }
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let cache = app_handle.state::<ThumbnailCache>();
+        cache.prefetch(urls, variant).await;
+    });
+}
+
+
+// --- Tauri Commands ---
+
+/// サムネイルのローカルパスを返す (フロントエンドでは convertFileSrc で表示する)
+#[tauri::command]
+pub async fn get_cached_thumbnail(
+    cache: State<'_, ThumbnailCache>,
+    url: String,
+    variant: Option<ThumbnailVariant>,
+) -> Result<String, String> {
+    let path = cache.get(&url, variant.unwrap_or_default()).await.map_err(|e| e.to_string())?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+pub async fn prefetch_thumbnails(
+    app_handle: AppHandle,
+    urls: Vec<String>,
+    variant: Option<ThumbnailVariant>,
+) -> Result<(), String> {
+    spawn_prefetch(&app_handle, urls, variant.unwrap_or_default());
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_thumbnail_cache_stats(cache: State<'_, ThumbnailCache>) -> Result<ThumbnailCacheStats, String> {
+    cache.stats().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn set_thumbnail_cache_budget(cache: State<'_, ThumbnailCache>, megabytes: u64) -> Result<(), String> {
+    let budget_bytes = megabytes.checked_mul(1024 * 1024)
+        .ok_or_else(|| format!("キャッシュの上限が大きすぎます: {} MB", megabytes))?;
+    cache.set_budget(budget_bytes).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn clear_thumbnail_cache(cache: State<'_, ThumbnailCache>) -> Result<(), String> {
+    cache.clear().map_err(|e| e.to_string())
+}
@@ -0,0 +1 @@
+pub mod cache;
\ No newline at end of file
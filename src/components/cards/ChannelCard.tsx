@@ -3,6 +3,8 @@ import { motion } from "framer-motion";
 import { PlayCircle, Users } from "lucide-react";
 import { Channel, NavigateFunction } from "@/types";
 import { itemVariants } from "@/config/animations";
+import { CachedImage } from "@/components/common/CachedImage";
+import { useCachedThumbnail } from "@/hooks/useCachedThumbnail";
 
 interface ChannelCardProps {
   channel: Channel;
@@ -10,6 +12,9 @@ interface ChannelCardProps {
 }
 
 export const ChannelCard: FC<ChannelCardProps> = ({ channel, navigate }) => {
+  const iconSrc = useCachedThumbnail(channel.icon);
+  const bannerSrc = useCachedThumbnail(channel.banner, "original");
+
   return (
     <motion.div
       variants={itemVariants}
@@ -17,14 +22,14 @@ export const ChannelCard: FC<ChannelCardProps> = ({ channel, navigate }) => {
     >
       <div className="h-32 md:h-40 relative">
         <img
-          src={channel.banner}
+          src={bannerSrc}
           alt={`${channel.name} banner`}
           className="w-full h-full object-cover"
         />
         <div className="absolute inset-0 bg-gradient-to-t from-neutral-800/80 to-transparent"></div>
         <motion.img
           layoutId={`channel-icon-${channel.id}`}
-          src={channel.icon}
+          src={iconSrc}
           alt={`${channel.name} icon`}
           className="absolute bottom-[-40px] left-6 w-20 h-20 rounded-full border-4 border-neutral-800 shadow-xl"
         />
@@ -57,7 +62,7 @@ export const ChannelCard: FC<ChannelCardProps> = ({ channel, navigate }) => {
                 className="relative rounded-lg overflow-hidden"
                 layoutId={`video-player-${video.id}`}
               >
-                <img
+                <CachedImage
                   src={video.thumbnail}
                   alt={video.title}
                   className="w-full aspect-video object-cover"
@@ -4,6 +4,7 @@ import { PlayCircle, Eye, Calendar } from "lucide-react";
 import { Video, NavigateFunction } from "@/types";
 import { itemVariants } from "@/config/animations";
 import { formatNumberWithSlashes } from "@/lib/utils";
+import { CachedImage } from "@/components/common/CachedImage";
 
 interface VideoCardProps {
   video: Video;
@@ -22,7 +23,7 @@ export const VideoCard: FC<VideoCardProps> = ({ video, navigate }) => (
       whileHover={{ scale: 1.05, y: -5 }}
       transition={{ type: "spring", stiffness: 200, damping: 20 }}
     >
-      <img
+      <CachedImage
         src={video.thumbnail}
         alt={video.title}
         className="w-full aspect-video object-cover"
@@ -0,0 +1,18 @@
+import { FC, ImgHTMLAttributes } from "react";
+import {
+  useCachedThumbnail,
+  ThumbnailVariant,
+} from "@/hooks/useCachedThumbnail";
+
+interface CachedImageProps extends ImgHTMLAttributes<HTMLImageElement> {
+  variant?: ThumbnailVariant;
+}
+
+export const CachedImage: FC<CachedImageProps> = ({
+  src,
+  variant,
+  ...props
+}) => {
+  const cachedSrc = useCachedThumbnail(src, variant);
+  return <img src={cachedSrc} {...props} />;
+};
@@ -7,7 +7,7 @@ import {
   FaCompress,
   FaCog,
 } from "react-icons/fa";
-import { invoke } from "@tauri-apps/api/core";
+import { invoke, convertFileSrc } from "@tauri-apps/api/core";
 import { useState, useRef, useEffect, FC } from "react";
 import { motion, AnimatePresence } from "framer-motion";
 import { useSettings } from "@/contexts/SettingsContext";
@@ -335,6 +335,70 @@ const getStreamingUrl = async (url: string, format: string) => {
   return streamUrl;
 };
 
+interface ResolvedStream {
+  format_id: string;
+  url: string;
+  expires_at: number | null;
+}
+
+interface ResolvedStreams {
+  format_id: string;
+  video: ResolvedStream | null;
+  audio: ResolvedStream | null;
+  expires_at: number | null;
+}
+
+// 映像と音声のURLを1回のyt-dlp呼び出しでまとめて取得する
+const resolveStreams = async (url: string, formatSelector: string) => {
+  const streams = await invoke<ResolvedStreams>("dlp_resolve_streams", {
+    videoUrl: url,
+    formatSelector: formatSelector,
+  });
+  console.log(`Resolved streams for ${formatSelector}:`, streams);
+  return streams;
+};
+
+interface OfflineSource {
+  video_id: string;
+  path: string;
+  ext: string;
+}
+
+// ダウンロード済みの動画があればローカルファイルのURLを返す
+const getOfflineUrl = async (url: string) => {
+  const source = await invoke<OfflineSource | null>("get_offline_source", {
+    videoUrl: url,
+  });
+  return source ? convertFileSrc(source.path) : null;
+};
+
+// 再生位置の記録に使う動画ID
+const getVideoId = (url: string): string | null => {
+  try {
+    const parsed = new URL(url);
+    if (parsed.hostname === "youtu.be") {
+      return parsed.pathname.slice(1) || null;
+    }
+    return parsed.searchParams.get("v");
+  } catch {
+    return null;
+  }
+};
+
+// 書き込みの間引きはRust側で行うので、timeupdateのたびに送ってよい
+const savePlaybackPosition = (
+  videoId: string,
+  video: HTMLVideoElement,
+  force: boolean
+) => {
+  invoke("update_playback_position", {
+    videoId,
+    position: video.currentTime,
+    duration: Number.isFinite(video.duration) ? video.duration : null,
+    force,
+  }).catch((error) => console.error("Failed to save playback position:", error));
+};
+
 const formatTime = (seconds: number): string => {
   const mins = Math.floor(seconds / 60);
   const secs = Math.floor(seconds % 60);
@@ -599,7 +663,38 @@ const NewPlayer: FC<NewPlayerProps> = ({
       setStreamVideoUrl("");
       setStreamAudioUrl("");
 
+      // 前回の続きから再生する (最初の "playing" でシークされる)
+      const videoId = getVideoId(youtubeUrl);
+      if (videoId) {
+        invoke<number | null>("get_resume_position", { videoId })
+          .then((position) => {
+            if (position) {
+              refreshRefTimeBackUp.current = position;
+            }
+          })
+          .catch((error) =>
+            console.warn("Failed to get resume position:", error)
+          );
+      }
+
       try {
+        // オフラインで再生できる場合は映像と音声に同じローカルファイルを使う
+        const offlineUrl = await getOfflineUrl(youtubeUrl).catch((error) => {
+          console.warn("Failed to check offline source:", error);
+          return null;
+        });
+        if (offlineUrl) {
+          console.log("Playing downloaded file:", offlineUrl);
+          setStreamVideoUrl(offlineUrl);
+          setStreamAudioUrl(offlineUrl);
+          setVolume(appSettings.settings.volume);
+          setIsMuted(appSettings.settings.volume === 0);
+          if (audioRef.current) {
+            audioRef.current.volume = appSettings.settings.volume;
+          }
+          return;
+        }
+
         const formatsResult = await getVideoFormats(youtubeUrl);
 
         if (
@@ -664,14 +759,12 @@ const NewPlayer: FC<NewPlayerProps> = ({
         setSelectedVideoFormat(selectedVideo.id);
         setSelectedAudioFormat(selectedAudio.id);
 
-        const videoStreamUrl = await getStreamingUrl(
+        const streams = await resolveStreams(
           youtubeUrl,
-          selectedVideo.id
-        );
-        const audioStreamUrl = await getStreamingUrl(
-          youtubeUrl,
-          selectedAudio.id
+          `${selectedVideo.id}+${selectedAudio.id}`
         );
+        const videoStreamUrl = streams.video?.url;
+        const audioStreamUrl = streams.audio?.url;
 
         if (!videoStreamUrl || !audioStreamUrl) {
           throw new Error("ストリーミングURLの取得に失敗しました");
@@ -702,6 +795,19 @@ const NewPlayer: FC<NewPlayerProps> = ({
 
       if (!video || !audio) return;
 
+      const videoId = getVideoId(youtubeUrl);
+      // シーク待ちの間は位置0を記録しない
+      const recordPosition = (force: boolean) => {
+        if (
+          !videoId ||
+          refreshRefTimeBackUp.current !== 0 ||
+          formatChangeRef.current
+        ) {
+          return;
+        }
+        savePlaybackPosition(videoId, video, force);
+      };
+
       const syncAudioToVideo = () => {
         if (Math.abs(audio.currentTime - video.currentTime) > 0.1) {
           audio.currentTime = video.currentTime;
@@ -711,6 +817,7 @@ const NewPlayer: FC<NewPlayerProps> = ({
       const handleTimeUpdate = () => {
         setCurrentTime(video.currentTime);
         syncAudioToVideo();
+        recordPosition(false);
       };
 
       const handleLoadedMetadata = () => {
@@ -748,8 +855,11 @@ const NewPlayer: FC<NewPlayerProps> = ({
       const handlePause = () => {
         setIsPlaying(false);
         audio.pause();
+        recordPosition(true);
       };
 
+      const handleEnded = () => recordPosition(true);
+
       const handleSeeking = () => {
         audio.currentTime = video.currentTime;
         audio.pause();
@@ -773,6 +883,21 @@ const NewPlayer: FC<NewPlayerProps> = ({
       video.addEventListener("seeking", handleSeeking);
       video.addEventListener("seeked", handleSeeked);
       video.addEventListener("stalled", handleStall);
+      video.addEventListener("ended", handleEnded);
+
+      return () => {
+        video.removeEventListener("timeupdate", handleTimeUpdate);
+        video.removeEventListener("loadedmetadata", handleLoadedMetadata);
+        video.removeEventListener("waiting", handleWaiting);
+        video.removeEventListener("playing", handlePlaying);
+        video.removeEventListener("pause", handlePause);
+        video.removeEventListener("seeking", handleSeeking);
+        video.removeEventListener("seeked", handleSeeked);
+        video.removeEventListener("stalled", handleStall);
+        video.removeEventListener("ended", handleEnded);
+        // 画面を離れるときの位置を残す
+        recordPosition(true);
+      };
     };
 
     if (youtubeUrl && !LoadedRef.current) {
@@ -0,0 +1,36 @@
+import { useEffect, useState } from "react";
+import { invoke, convertFileSrc } from "@tauri-apps/api/core";
+
+export type ThumbnailVariant = "small" | "medium" | "large" | "original";
+
+// サムネイルをRust側のキャッシュ経由で表示する
+// キャッシュ取得に失敗した場合は元のURLをそのまま使う
+export const useCachedThumbnail = (
+  url: string | undefined,
+  variant: ThumbnailVariant = "medium"
+) => {
+  const [src, setSrc] = useState<string | undefined>(undefined);
+
+  useEffect(() => {
+    if (!url) {
+      setSrc(undefined);
+      return;
+    }
+
+    let cancelled = false;
+    invoke<string>("get_cached_thumbnail", { url, variant })
+      .then((path) => {
+        if (!cancelled) setSrc(convertFileSrc(path));
+      })
+      .catch((error) => {
+        console.warn("Failed to load cached thumbnail:", error);
+        if (!cancelled) setSrc(url);
+      });
+
+    return () => {
+      cancelled = true;
+    };
+  }, [url, variant]);
+
+  return src;
+};
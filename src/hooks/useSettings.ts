@@ -1,10 +1,18 @@
 // src/hooks/useSettings.ts
 import { useState, useEffect } from "react";
 import { LazyStore } from "@tauri-apps/plugin-store";
+import { listen } from "@tauri-apps/api/event";
+import { invoke } from "@tauri-apps/api/core";
 import { AppSettings, defaultAppSettings, Channel } from "@/types";
 
 const STORE_FILE = "app-settings.json";
 
+// フォロー・プレイリスト・履歴はRust側のSQLiteに保存する
+const loadFollowChannel = () =>
+  invoke<AppSettings["followChannel"]>("list_subscriptions");
+const loadPlaylist = () => invoke<AppSettings["playlist"]>("list_playlists");
+const loadHistory = () => invoke<AppSettings["history"]>("list_history");
+
 export const useAppSettings = () => {
   const [appSettings, setAppSettings] =
     useState<AppSettings>(defaultAppSettings);
@@ -27,6 +35,33 @@ export const useAppSettings = () => {
 
     initStore();
   }, []);
+
+  // Rust側の取り込みでフォロー・履歴・プレイリストが増えたときに読み直す
+  useEffect(() => {
+    const unlistenFollow = listen("follow_channels_changed", async () => {
+      const followChannel = await loadFollowChannel();
+      setAppSettings((prev) => ({ ...prev, followChannel }));
+    });
+    const unlistenHistory = listen("history_changed", async () => {
+      const history = await loadHistory();
+      setAppSettings((prev) => ({ ...prev, history }));
+    });
+    const unlistenPlaylist = listen("playlists_changed", async () => {
+      const playlist = await loadPlaylist();
+      setAppSettings((prev) => ({ ...prev, playlist }));
+    });
+    // 置き換えの復元では基本設定も変わる
+    const unlistenBackup = listen("backup_restored", async () => {
+      setAppSettings(await loadAllSettings(store));
+    });
+
+    return () => {
+      unlistenFollow.then((fn) => fn());
+      unlistenHistory.then((fn) => fn());
+      unlistenPlaylist.then((fn) => fn());
+      unlistenBackup.then((fn) => fn());
+    };
+  }, []);
   
   // 全設定の読み込み
   const loadAllSettings = async (
@@ -36,16 +71,11 @@ export const useAppSettings = () => {
       const settings =
         ((await storeInstance.get("settings")) as AppSettings["settings"]) ||
         defaultAppSettings.settings;
-      const followChannel =
-        ((await storeInstance.get(
-          "followChannel"
-        )) as AppSettings["followChannel"]) || defaultAppSettings.followChannel; // 空配列を使用
-      const playlist =
-        ((await storeInstance.get("playlist")) as AppSettings["playlist"]) ||
-        defaultAppSettings.playlist;
-      const history =
-        ((await storeInstance.get("history")) as AppSettings["history"]) ||
-        defaultAppSettings.history;
+      const [followChannel, playlist, history] = await Promise.all([
+        loadFollowChannel(),
+        loadPlaylist(),
+        loadHistory(),
+      ]);
       const state =
         ((await storeInstance.get("state")) as AppSettings["state"]) ||
         defaultAppSettings.state;
@@ -105,8 +135,7 @@ export const useAppSettings = () => {
       };
       const updatedFollowChannels = [...appSettings.followChannel, newChannel];
 
-      await store.set("followChannel", updatedFollowChannels);
-      await store.save();
+      await invoke("add_subscription", { channelId, channelName });
 
       setAppSettings((prev) => ({
         ...prev,
@@ -128,8 +157,10 @@ export const useAppSettings = () => {
         return c;
       });
 
-      await store.set("followChannel", updatedFollowChannels);
-      await store.save();
+      await invoke("set_subscription_cache", {
+        channelId: channel.id,
+        cache: channel,
+      });
 
       setAppSettings((prev) => ({
         ...prev,
@@ -149,8 +180,7 @@ export const useAppSettings = () => {
         (channel) => channel.id !== channelId
       );
 
-      await store.set("followChannel", updatedFollowChannels);
-      await store.save();
+      await invoke("remove_subscription", { channelId });
 
       setAppSettings((prev) => ({
         ...prev,
@@ -179,8 +209,7 @@ export const useAppSettings = () => {
       const [movedChannel] = updatedFollowChannels.splice(channelIndex, 1);
       updatedFollowChannels.splice(moveIndexx, 0, movedChannel);
 
-      await store.set("followChannel", updatedFollowChannels);
-      await store.save();
+      await invoke("move_subscription", { channelId, index: moveIndexx });
 
       setAppSettings((prev) => ({
         ...prev,
@@ -204,8 +233,7 @@ export const useAppSettings = () => {
         [playlistId]: playlistData,
       };
 
-      await store.set("playlist", updatedPlaylists);
-      await store.save();
+      await invoke("save_playlist", { playlistId, playlist: playlistData });
 
       setAppSettings((prev) => ({
         ...prev,
@@ -238,8 +266,11 @@ export const useAppSettings = () => {
         [playlistId]: updatedPlaylist,
       };
 
-      await store.set("playlist", updatedPlaylists);
-      await store.save();
+      await invoke("add_playlist_item", {
+        playlistId,
+        videoId,
+        video: videoData,
+      });
 
       setAppSettings((prev) => ({
         ...prev,
@@ -271,8 +302,7 @@ export const useAppSettings = () => {
         [playlistId]: updatedPlaylist,
       };
 
-      await store.set("playlist", updatedPlaylists);
-      await store.save();
+      await invoke("remove_playlist_item", { playlistId, videoId });
 
       setAppSettings((prev) => ({
         ...prev,
@@ -291,8 +321,7 @@ export const useAppSettings = () => {
       const updatedPlaylists = { ...appSettings.playlist };
       delete updatedPlaylists[playlistId];
 
-      await store.set("playlist", updatedPlaylists);
-      await store.save();
+      await invoke("remove_playlist", { playlistId });
 
       setAppSettings((prev) => ({
         ...prev,
@@ -350,14 +379,10 @@ export const useAppSettings = () => {
           const updatedHistory = [newHistoryEntry, ...editedHistory];
           console.log("Updated history:", updatedHistory);
           // storeの更新は非同期で実行
-          (async () => {
-            try {
-              await store.set("history", updatedHistory);
-              await store.save();
-            } catch (error) {
-              console.error("Failed to save history:", error);
-            }
-          })();
+          // DB側は動画ID (無ければタイトル) で重複をまとめる
+          invoke("add_history", { entry: newHistoryEntry }).catch((error) =>
+            console.error("Failed to save history:", error)
+          );
 
           return {
             ...prev,
@@ -365,14 +390,10 @@ export const useAppSettings = () => {
           };
         } catch (error) {
           const updatedHistory = [newHistoryEntry];
-          (async () => {
-            try {
-              await store.set("history", updatedHistory);
-              await store.save();
-            } catch (error) {
-              console.error("Failed to save history:", error);
-            }
-          })();
+          // DB側は動画ID (無ければタイトル) で重複をまとめる
+          invoke("add_history", { entry: newHistoryEntry }).catch((error) =>
+            console.error("Failed to save history:", error)
+          );
           return {
             ...prev,
             history: updatedHistory,
@@ -389,10 +410,10 @@ export const useAppSettings = () => {
     if (!store || index < 0 || index >= appSettings.history.length) return;
 
     try {
+      const target = appSettings.history[index];
       const updatedHistory = appSettings.history.filter((_, i) => i !== index);
 
-      await store.set("history", updatedHistory);
-      await store.save();
+      await invoke("remove_history", { url: target.url, title: target.title });
 
       setAppSettings((prev) => ({
         ...prev,
@@ -408,8 +429,7 @@ export const useAppSettings = () => {
     if (!store) return;
 
     try {
-      await store.set("history", []);
-      await store.save();
+      await invoke("clear_history");
 
       setAppSettings((prev) => ({
         ...prev,
@@ -437,6 +457,15 @@ export const useAppSettings = () => {
     try {
       await store.clear();
       await store.save();
+      await invoke("clear_history");
+      await Promise.all([
+        ...appSettings.followChannel.map((channel) =>
+          invoke("remove_subscription", { channelId: channel.id })
+        ),
+        ...Object.keys(appSettings.playlist).map((playlistId) =>
+          invoke("remove_playlist", { playlistId })
+        ),
+      ]);
       setAppSettings(defaultAppSettings);
     } catch (error) {
       console.error("Failed to reset settings:", error);
@@ -1,5 +1,23 @@
 import { invoke } from "@tauri-apps/api/core";
-import { Channel, Video } from "@/types";
+import {
+  BackupImportReport,
+  BackupManifest,
+  Channel,
+  ContinueWatchingEntry,
+  DataImportReport,
+  FeedPage,
+  FeedQuery,
+  HistoryImportReport,
+  InstalledVersions,
+  RestoreMode,
+  SearchQuery,
+  SearchResult,
+  SubscriptionImportReport,
+  UpdateInfo,
+  UpdaterSettings,
+  Video,
+  WatchProgress,
+} from "@/types";
 
 export const apiService = {
   async getChannelInfo(channelId: string): Promise<Channel> {
@@ -64,4 +82,117 @@ export const apiService = {
     });
     return JSON.parse(result);
   },
+
+  // チャンネルごとの途中結果は "subscription_feed_partial" イベントで届く
+  async getSubscriptionFeed(query: FeedQuery = {}): Promise<FeedPage> {
+    return await invoke<FeedPage>("get_subscription_feed", { query });
+  },
+
+  async markWatched(videoId: string, watched = true): Promise<void> {
+    await invoke("mark_watched", { videoId, watched });
+  },
+
+  async getWatchProgress(
+    videoIds: string[]
+  ): Promise<Record<string, WatchProgress>> {
+    return await invoke<Record<string, WatchProgress>>("get_watch_progress", {
+      videoIds,
+    });
+  },
+
+  async getContinueWatching(limit?: number): Promise<ContinueWatchingEntry[]> {
+    return await invoke<ContinueWatchingEntry[]>("get_continue_watching", {
+      limit,
+    });
+  },
+
+  // 履歴・プレイリスト・フォロー中のチャンネル・見かけた動画をまとめて探す
+  async searchLocal(query: SearchQuery): Promise<SearchResult[]> {
+    return await invoke<SearchResult[]>("search_local", { query });
+  },
+
+  async getYtDlpUpdaterSettings(): Promise<UpdaterSettings> {
+    return await invoke<UpdaterSettings>("get_ytdlp_updater_settings");
+  },
+
+  async setYtDlpUpdaterSettings(settings: UpdaterSettings): Promise<void> {
+    await invoke("set_ytdlp_updater_settings", { settings });
+  },
+
+  async getYtDlpVersions(): Promise<InstalledVersions> {
+    return await invoke<InstalledVersions>("get_ytdlp_versions");
+  },
+
+  // 戻したバージョンに固定される
+  async rollbackYtDlp(): Promise<string> {
+    return await invoke<string>("rollback_ytdlp");
+  },
+
+  // インストールはせずに確認だけする
+  async checkYtDlpUpdate(): Promise<UpdateInfo> {
+    return await invoke<UpdateInfo>("check_for_update");
+  },
+
+  async getLastYtDlpUpdateCheck(): Promise<UpdateInfo | null> {
+    return await invoke<UpdateInfo | null>("get_last_ytdlp_update_check");
+  },
+
+  // SHA2-256SUMS の署名とハッシュで検証してからインストールし、バージョンを返す
+  async installYtDlpFromFile(executablePath: string, hashesPath: string, signaturePath: string, version?: string): Promise<string> {
+    return await invoke<string>("install_ytdlp_from_file", { executablePath, hashesPath, signaturePath, version });
+  },
+
+  async exportBackup(outputPath: string): Promise<BackupManifest> {
+    return await invoke<BackupManifest>("export_backup", { outputPath });
+  },
+
+  // 復元後は "backup_restored" イベントが届く
+  async importBackup(
+    sourcePath: string,
+    mode: RestoreMode = "merge"
+  ): Promise<BackupImportReport> {
+    return await invoke<BackupImportReport>("import_backup", {
+      sourcePath,
+      mode,
+    });
+  },
+
+  async exportSubscriptionsOpml(outputPath: string): Promise<number> {
+    return await invoke<number>("export_subscriptions_opml", { outputPath });
+  },
+
+  // 進捗は "subscription_import_progress" イベントで届く
+  async importSubscriptionsOpml(
+    sourcePath: string
+  ): Promise<SubscriptionImportReport> {
+    return await invoke<SubscriptionImportReport>("import_subscriptions_opml", {
+      sourcePath,
+    });
+  },
+
+  async importTakeoutSubscriptions(
+    sourcePath: string
+  ): Promise<SubscriptionImportReport> {
+    return await invoke<SubscriptionImportReport>(
+      "import_takeout_subscriptions",
+      { sourcePath }
+    );
+  },
+
+  // watch-history.json と watch-history.html のどちらにも対応
+  async importTakeoutHistory(sourcePath: string): Promise<HistoryImportReport> {
+    return await invoke<HistoryImportReport>("import_takeout_history", {
+      sourcePath,
+    });
+  },
+
+  // profiles.db / history.db / playlists.db のどれでもよい
+  async importFreeTubeDb(sourcePath: string): Promise<DataImportReport> {
+    return await invoke<DataImportReport>("import_freetube_db", { sourcePath });
+  },
+
+  // 購読のJSON、またはデータベースのzip
+  async importNewPipe(sourcePath: string): Promise<DataImportReport> {
+    return await invoke<DataImportReport>("import_newpipe", { sourcePath });
+  },
 };
@@ -19,6 +19,169 @@ export interface Video {
   date?: string;
 }
 
+// Rust側の subscription::video::ChannelVideo
+export interface ChannelVideo {
+  video_id: string;
+  url: string;
+  title: string;
+  channel_id: string;
+  channel_name?: string;
+  duration?: number;
+  timestamp?: number; // UNIX秒
+  thumbnail?: string;
+  view_count?: number;
+  live_status?: string;
+}
+
+export interface FeedQuery {
+  page?: number;
+  page_size?: number;
+  videos_per_channel?: number;
+  hide_shorts?: boolean;
+  hide_watched?: boolean;
+  hide_live?: boolean;
+  force_refresh?: boolean;
+}
+
+export interface FeedPage {
+  videos: ChannelVideo[];
+  page: number;
+  page_size: number;
+  total: number;
+  has_more: boolean;
+  failed_channels: string[];
+  progress: Record<string, WatchProgress>; // 動画IDがキー
+}
+
+// Rust側の db::playback::WatchProgress
+export interface WatchProgress {
+  video_id: string;
+  position: number; // 秒
+  duration?: number; // 秒
+  watched: boolean;
+  percent: number; // 0-100
+  updated_at: number; // ミリ秒
+}
+
+// Rust側の dlp::dlp_manager
+export type UpdateChannel = "stable" | "nightly" | "master";
+
+export interface UpdaterSettings {
+  channel: UpdateChannel;
+  pinned_version?: string | null; // タグ (例: "2024.03.10")
+  api_base_url?: string | null; // GitHub互換のリリースAPI (無ければ api.github.com)
+  repo?: string | null; // "owner/name" (無ければ系統ごとの既定)
+  github_token?: string | null; // 無ければ環境変数 GITHUB_TOKEN
+  signing_key_path?: string | null; // 組み込みの鍵に加えて信頼する公開鍵のファイル
+  check_interval_hours?: number | null; // バックグラウンドで確認する間隔 (無ければ確認しない)
+  auto_install: boolean; // 確認で見つかった更新をそのままインストールする
+  update_on_extractor_error: boolean; // 抽出に失敗したら更新してやり直す
+}
+
+// check_for_update の結果 ("ytdlp_update_available" イベントでも届く)
+export interface UpdateInfo {
+  current?: string | null;
+  latest: string;
+  update_available: boolean;
+  pinned: boolean;
+  release_notes?: string | null; // Markdown
+  release_url?: string | null;
+  published_at?: string | null;
+  checked_at: number; // 秒
+}
+
+export interface InstalledVersions {
+  current?: string;
+  previous?: string; // ロールバックで戻せるバージョン
+}
+
+// "ytdlp_download_progress" イベント (Rust側の dlp::transfer::TransferProgress)
+export interface TransferProgress {
+  file_name: string;
+  downloaded_bytes: number;
+  total_bytes?: number;
+  bytes_per_second: number;
+  eta?: number; // 秒
+  resumed_from: number;
+}
+
+// Rust側の backup::archive
+export interface BackupManifest {
+  format: string;
+  format_version: number;
+  app_version: string;
+  schema_version: number;
+  created_at: number; // ミリ秒
+  files: { name: string; size: number; sha256: string }[];
+}
+
+export type RestoreMode = "merge" | "replace";
+
+export interface BackupImportReport {
+  mode: RestoreMode;
+  app_version: string;
+  created_at: number;
+  schema_version: number;
+  subscriptions: number;
+  playlists: number;
+  playlist_items: number;
+  history: number;
+  playback_positions: number;
+  video_metadata: number;
+  download_rules: number;
+  settings_restored: boolean;
+}
+
+// Rust側の db::search
+export type SearchKind = "history" | "playlist_item" | "channel" | "video";
+
+export interface SearchQuery {
+  text: string;
+  kinds?: SearchKind[]; // 省略時は全種類
+  limit?: number;
+}
+
+export interface SearchResult {
+  kind: SearchKind;
+  video_id?: string;
+  channel_id?: string;
+  playlist_id?: string;
+  url: string;
+  title: string;
+  channel_name: string;
+  snippet?: string; // 一致部分は [ ] で囲まれる
+}
+
+export interface ContinueWatchingEntry extends WatchProgress {
+  title: string;
+  url: string;
+  channel_id: string;
+  channel_name: string;
+}
+
+export interface FollowedChannel {
+  id: string;
+  channelName: string;
+}
+
+export interface SubscriptionImportReport {
+  added: FollowedChannel[];
+  skipped: FollowedChannel[];
+  failed: { title: string; url?: string; error: string }[];
+}
+
+export interface HistoryImportReport {
+  added: number;
+  skipped: number;
+}
+
+// ファイルに含まれなかった種類は null
+export interface DataImportReport {
+  subscriptions: SubscriptionImportReport | null;
+  history: HistoryImportReport | null;
+  playlists: { playlists: number; videos: number } | null;
+}
+
 export interface emitter {
   status: string; // "success" or "error"
   progress: number; // 進捗率（0-100）